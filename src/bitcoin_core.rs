@@ -0,0 +1,414 @@
+//! Amount handling that follows the rules of Bitcoin Core rather than the
+//! rules of this crate.
+
+use {BitcoinQuantity, ParseError, SignedBitcoinQuantity, SATS_PER_BITCOIN};
+
+#[cfg(feature = "rpc")]
+const DECIMALS: i64 = 8;
+
+/// The characters trimmed by Core's `TrimString` and matched by `IsSpace`.
+const WHITESPACE: &[char] = &[' ', '\x0c', '\n', '\r', '\t', '\x0b'];
+
+impl BitcoinQuantity {
+    /// Parses an amount the way Bitcoin Core's `ParseMoney` does for options
+    /// such as `-maxtxfee=0.1` or `-paytxfee`.
+    ///
+    /// Leading and trailing whitespace is ignored. The amount is a decimal
+    /// number of bitcoin with at most 10 integer digits and 8 decimal places,
+    /// where either side of the decimal point may be empty (`"1."`, `".5"`).
+    /// Signs, exponents and embedded whitespace are rejected, as are amounts
+    /// above `MAX_MONEY`.
+    pub fn parse_money(string: &str) -> Result<BitcoinQuantity, ParseError> {
+        let string = string.trim_matches(WHITESPACE);
+        if string.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        if string.starts_with('-') {
+            return Err(ParseError::Negative);
+        }
+
+        let (whole, fraction) = match string.find('.') {
+            Some(index) => (&string[..index], &string[index + 1..]),
+            None => (string, ""),
+        };
+        if let Some(c) = whole
+            .chars()
+            .chain(fraction.chars())
+            .find(|c| !c.is_ascii_digit())
+        {
+            return Err(ParseError::InvalidCharacter(c));
+        }
+        if fraction.len() > 8 {
+            return Err(ParseError::TooManyDecimalPlaces);
+        }
+        // Core's guard against overflowing an int64, which also rejects
+        // leading zeros beyond 10 digits.
+        if whole.len() > 10 {
+            return Err(ParseError::Overflow);
+        }
+
+        let whole = ::parse_digits(whole)?;
+        let fraction = fraction
+            .bytes()
+            .chain(::std::iter::repeat(b'0'))
+            .take(8)
+            .fold(0u64, |acc, digit| acc * 10 + u64::from(digit - b'0'));
+
+        Ok(BitcoinQuantity::try_from_satoshi(
+            whole * SATS_PER_BITCOIN + fraction,
+        )?)
+    }
+
+    /// Formats the amount the way Bitcoin Core's `FormatMoney` does: in
+    /// bitcoin without unit, with trailing zeros trimmed but at least two
+    /// decimal places, e.g. `"1.00"`, `"0.10"` or `"12345.6789"`.
+    pub fn format_money(self) -> String {
+        format_money(
+            self.satoshi() / SATS_PER_BITCOIN,
+            self.satoshi() % SATS_PER_BITCOIN,
+        )
+    }
+}
+
+impl SignedBitcoinQuantity {
+    /// Formats the amount the way Bitcoin Core's `FormatMoney` does, with a
+    /// leading `-` for negative amounts, e.g. `"-1.00"`.
+    pub fn format_money(self) -> String {
+        let formatted = self.unsigned_abs().format_money();
+        if self.is_negative() {
+            format!("-{}", formatted)
+        } else {
+            formatted
+        }
+    }
+}
+
+fn format_money(whole: u64, fraction: u64) -> String {
+    let fraction = format!("{:08}", fraction);
+    let fraction = fraction.trim_end_matches('0');
+    format!("{}.{:0<2}", whole, fraction)
+}
+
+/// Parses a decimal number the way Bitcoin Core's `ParseFixedPoint` does for
+/// 8 decimals, as used by `AmountFromValue` for JSON-RPC amounts.
+///
+/// Exponent notation such as `1e-8` is accepted and trailing zeros do not
+/// count as decimal places, but any non-zero digit below a satoshi is
+/// rejected. Like Core, the integer part may only start with `0` if it is a
+/// single `0`. The result must be within `MAX_MONEY`.
+#[cfg(feature = "rpc")]
+pub(crate) fn parse_fixed_point(string: &str) -> Result<BitcoinQuantity, ParseError> {
+    if string.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let (negative, unsigned) = match string.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, string),
+    };
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(index) => (&unsigned[..index], parse_exponent(&unsigned[index + 1..])?),
+        None => (unsigned, 0),
+    };
+    let (integer, fraction) = match mantissa.find('.') {
+        Some(index) => (&mantissa[..index], &mantissa[index + 1..]),
+        None => (mantissa, ""),
+    };
+
+    if integer.is_empty() {
+        return Err(ParseError::InvalidCharacter(
+            unsigned.chars().next().unwrap_or('-'),
+        ));
+    }
+    if mantissa.contains('.') && fraction.is_empty() {
+        return Err(ParseError::InvalidCharacter('.'));
+    }
+    if let Some(c) = integer
+        .chars()
+        .chain(fraction.chars())
+        .find(|c| !c.is_ascii_digit())
+    {
+        return Err(ParseError::InvalidCharacter(c));
+    }
+    if integer.len() > 1 && integer.starts_with('0') {
+        return Err(ParseError::InvalidCharacter('0'));
+    }
+
+    // Core skips a lone leading `0`, so it does not count as a trailing zero.
+    let digits = match integer {
+        "0" => fraction.to_string(),
+        _ => format!("{}{}", integer, fraction),
+    };
+    let significant = digits.trim_end_matches('0');
+    let trailing_zeros = (digits.len() - significant.len()) as i64;
+    let shift = exponent - fraction.len() as i64 + trailing_zeros + DECIMALS;
+
+    let significant = significant.trim_start_matches('0');
+    if negative && !significant.is_empty() {
+        return Err(ParseError::Negative);
+    }
+    // Like Core, the exponent has to be in range even if the value is zero.
+    if shift < 0 {
+        return Err(ParseError::TooManyDecimalPlaces);
+    }
+    if shift >= 18 {
+        return Err(ParseError::Overflow);
+    }
+    if significant.is_empty() {
+        return Ok(BitcoinQuantity::ZERO);
+    }
+
+    let sats = ::parse_digits(significant)?
+        .checked_mul(10u64.pow(shift as u32))
+        .ok_or(ParseError::Overflow)?;
+
+    Ok(BitcoinQuantity::try_from_satoshi(sats)?)
+}
+
+#[cfg(feature = "rpc")]
+fn parse_exponent(string: &str) -> Result<i64, ParseError> {
+    let (negative, digits) = match string.chars().next() {
+        Some('-') => (true, &string[1..]),
+        Some('+') => (false, &string[1..]),
+        _ => (false, string),
+    };
+
+    if digits.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_digit()) {
+        return Err(ParseError::InvalidCharacter(c));
+    }
+    let digits = digits.trim_start_matches('0');
+    if digits.len() > 9 {
+        return Err(ParseError::Overflow);
+    }
+
+    let exponent = ::parse_digits(digits)? as i64;
+    Ok(if negative { -exponent } else { exponent })
+}
+
+/// Formats an amount the way Bitcoin Core's `ValueFromAmount` does: in
+/// bitcoin with exactly 8 decimal places, e.g. `0.00012345`.
+#[cfg(feature = "rpc")]
+pub(crate) fn value_from_amount(quantity: BitcoinQuantity) -> String {
+    quantity.to_fixed_bitcoin(8)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate spectral;
+
+    use self::spectral::prelude::*;
+    use super::*;
+
+    const COIN: u64 = 100_000_000;
+
+    // Ported from `util_ParseMoney` in Bitcoin Core's `util_tests.cpp`.
+    #[test]
+    fn parse_money_core_vectors() {
+        for &(input, sats) in &[
+            ("0.0", 0),
+            (".", 0),
+            ("0.", 0),
+            (".0", 0),
+            (".6789", 6789_0000),
+            ("12345.", COIN * 12345),
+            ("12345.6789", (COIN / 10000) * 123_456_789),
+            ("10000000.00", COIN * 10_000_000),
+            ("1000000.00", COIN * 1_000_000),
+            ("100000.00", COIN * 100_000),
+            ("10000.00", COIN * 10000),
+            ("1000.00", COIN * 1000),
+            ("100.00", COIN * 100),
+            ("10.00", COIN * 10),
+            ("1.00", COIN),
+            ("1", COIN),
+            ("   1", COIN),
+            ("1   ", COIN),
+            ("  1 ", COIN),
+            ("0.1", COIN / 10),
+            ("0.01", COIN / 100),
+            ("0.001", COIN / 1000),
+            ("0.0001", COIN / 10000),
+            ("0.00001", COIN / 100_000),
+            ("0.000001", COIN / 1_000_000),
+            ("0.0000001", COIN / 10_000_000),
+            ("0.00000001", COIN / 100_000_000),
+            (" 0.00000001 ", COIN / 100_000_000),
+            ("0.00000001 ", COIN / 100_000_000),
+            (" 0.00000001", COIN / 100_000_000),
+        ] {
+            assert_that(&BitcoinQuantity::parse_money(input))
+                .is_ok_containing(BitcoinQuantity::from_satoshi(sats));
+        }
+
+        for input in &[
+            // Parsing amount that cannot be represented should fail
+            "100000000.00",
+            "0.000000001",
+            // Parsing empty string should fail
+            "",
+            " ",
+            "  ",
+            // Parsing two numbers should fail
+            "..",
+            "0..0",
+            "1 2",
+            " 1 2 ",
+            " 1.2 3 ",
+            " 1 2.3 ",
+            // Embedded whitespace should fail
+            " -1 .2  ",
+            "  1 .2  ",
+            " +1 .2  ",
+            // Attempted 63 bit overflow should fail
+            "92233720368.54775808",
+            // Parsing negative amounts must fail
+            "-1",
+            // Parsing strings with embedded NUL characters should fail
+            "\0-1",
+            "1\0",
+        ] {
+            assert_that(&BitcoinQuantity::parse_money(input)).is_err();
+        }
+    }
+
+    #[test]
+    fn parse_money_errors() {
+        for &(input, ref error) in &[
+            (" ", ParseError::Empty),
+            ("-1", ParseError::Negative),
+            ("1e8", ParseError::InvalidCharacter('e')),
+            ("0.000000001", ParseError::TooManyDecimalPlaces),
+            ("00000000001", ParseError::Overflow),
+            ("21000000.00000001", ParseError::ExceedsMaxMoney),
+        ] {
+            assert_that(&BitcoinQuantity::parse_money(input)).is_err_containing(error);
+        }
+    }
+
+    // Ported from `util_FormatMoney` in Bitcoin Core's `util_tests.cpp`.
+    #[test]
+    fn format_money_core_vectors() {
+        for &(sats, formatted) in &[
+            (0, "0.00"),
+            ((COIN / 10000) * 123_456_789, "12345.6789"),
+            (COIN * 100_000_000, "100000000.00"),
+            (COIN * 10_000_000, "10000000.00"),
+            (COIN * 1_000_000, "1000000.00"),
+            (COIN * 100_000, "100000.00"),
+            (COIN * 10000, "10000.00"),
+            (COIN * 1000, "1000.00"),
+            (COIN * 100, "100.00"),
+            (COIN * 10, "10.00"),
+            (COIN, "1.00"),
+            (COIN / 10, "0.10"),
+            (COIN / 100, "0.01"),
+            (COIN / 1000, "0.001"),
+            (COIN / 10000, "0.0001"),
+            (COIN / 100_000, "0.00001"),
+            (COIN / 1_000_000, "0.000001"),
+            (COIN / 10_000_000, "0.0000001"),
+            (COIN / 100_000_000, "0.00000001"),
+            (i64::MAX as u64, "92233720368.54775807"),
+            (i64::MAX as u64 - 1, "92233720368.54775806"),
+            (i64::MAX as u64 - 2, "92233720368.54775805"),
+            (i64::MAX as u64 - 3, "92233720368.54775804"),
+        ] {
+            assert_eq!(
+                BitcoinQuantity::from_satoshi(sats).format_money(),
+                formatted
+            );
+        }
+
+        for &(sats, formatted) in &[
+            (-(COIN as i64), "-1.00"),
+            (i64::MIN + 3, "-92233720368.54775805"),
+            (i64::MIN + 2, "-92233720368.54775806"),
+            (i64::MIN + 1, "-92233720368.54775807"),
+            (i64::MIN, "-92233720368.54775808"),
+        ] {
+            assert_eq!(
+                SignedBitcoinQuantity::from_satoshi(sats).format_money(),
+                formatted
+            );
+        }
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn parse_fixed_point_accepts_core_amounts() {
+        for &(input, sats) in &[
+            ("0", 0),
+            ("-0", 0),
+            ("0.00012345", 12_345),
+            ("1", 100_000_000),
+            ("1.00000000000", 100_000_000),
+            ("1e-8", 1),
+            ("0.1E1", 100_000_000),
+            ("12345e-8", 12_345),
+            ("1e-0000000008", 1),
+            ("0.5e+00", 50_000_000),
+            ("0e9", 0),
+            ("21000000", 2_100_000_000_000_000),
+        ] {
+            assert_that(&parse_fixed_point(input))
+                .is_ok_containing(BitcoinQuantity::from_satoshi(sats));
+        }
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn parse_fixed_point_rejects_invalid_amounts() {
+        for &(input, ref error) in &[
+            ("", ParseError::Empty),
+            ("0.000000001", ParseError::TooManyDecimalPlaces),
+            ("1e-9", ParseError::TooManyDecimalPlaces),
+            ("-0.00000001", ParseError::Negative),
+            ("21000000.00000001", ParseError::ExceedsMaxMoney),
+            ("1e20", ParseError::Overflow),
+            ("1.", ParseError::InvalidCharacter('.')),
+            (".1", ParseError::InvalidCharacter('.')),
+            ("1x", ParseError::InvalidCharacter('x')),
+            ("01", ParseError::InvalidCharacter('0')),
+            ("0001", ParseError::InvalidCharacter('0')),
+            ("00.5", ParseError::InvalidCharacter('0')),
+            // the invalid values from Core's `util_tests`
+            ("-", ParseError::InvalidCharacter('-')),
+            ("a-1000", ParseError::InvalidCharacter('a')),
+            ("-a1000", ParseError::InvalidCharacter('a')),
+            ("-1000a", ParseError::InvalidCharacter('a')),
+            ("-01000", ParseError::InvalidCharacter('0')),
+            ("00.1", ParseError::InvalidCharacter('0')),
+            ("--0.1", ParseError::InvalidCharacter('-')),
+            ("-0.000000001", ParseError::Negative),
+            ("0.00000001000000001", ParseError::TooManyDecimalPlaces),
+            ("-99999999999999999999", ParseError::Negative),
+            ("99999999999999999999", ParseError::Overflow),
+            ("99999999999999999999e-8", ParseError::Overflow),
+            ("1e", ParseError::Empty),
+            ("1e-", ParseError::Empty),
+            ("1e+", ParseError::Empty),
+            ("1.1e", ParseError::Empty),
+            ("92233720368.54775808", ParseError::ExceedsMaxMoney),
+            ("0e20", ParseError::Overflow),
+            ("0e-9", ParseError::TooManyDecimalPlaces),
+        ] {
+            assert_that(&parse_fixed_point(input)).is_err_containing(error);
+        }
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn value_from_amount_has_eight_decimals() {
+        assert_eq!(
+            value_from_amount(BitcoinQuantity::from_satoshi(12_345)),
+            "0.00012345"
+        );
+        assert_eq!(
+            value_from_amount(BitcoinQuantity::MAX_MONEY),
+            "21000000.00000000"
+        );
+    }
+}
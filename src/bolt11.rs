@@ -0,0 +1,186 @@
+//! The amount in the human-readable part of a BOLT11 invoice: a positive
+//! integer number of bitcoin, optionally followed by a multiplier, e.g.
+//! `2500u` for 2500 micro-bitcoin.
+
+use {parse_digits, BitcoinQuantity, MilliSatoshiQuantity, ParseError};
+
+/// The multipliers in order of decreasing size, with the number of pico-bitcoin
+/// they stand for.
+const MULTIPLIERS: &[(&str, u128)] = &[
+    ("", 1_000_000_000_000),
+    ("m", 1_000_000_000),
+    ("u", 1_000_000),
+    ("n", 1_000),
+    ("p", 1),
+];
+
+const PICOS_PER_MSAT: u128 = 10;
+
+impl MilliSatoshiQuantity {
+    /// Parses a BOLT11 amount such as `"20m"`, `"10n"` or `"2500p"`.
+    ///
+    /// The number must be a positive integer without leading zeros. Pico
+    /// amounts must be a multiple of 10 because they cannot express less than
+    /// a millisatoshi. Amounts above `MAX_MONEY` are rejected.
+    pub fn from_bolt11_amount(string: &str) -> Result<MilliSatoshiQuantity, ParseError> {
+        let (digits, picos_per_unit) = match MULTIPLIERS[1..]
+            .iter()
+            .find(|&&(multiplier, _)| string.ends_with(multiplier))
+        {
+            Some(&(multiplier, picos_per_unit)) => {
+                (&string[..string.len() - multiplier.len()], picos_per_unit)
+            }
+            None => (string, MULTIPLIERS[0].1),
+        };
+
+        if digits.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(ParseError::InvalidCharacter(c));
+        }
+        if digits.starts_with('0') {
+            return Err(ParseError::InvalidCharacter('0'));
+        }
+
+        let picos = u128::from(parse_digits(digits)?) * picos_per_unit;
+        if !picos.is_multiple_of(PICOS_PER_MSAT) {
+            return Err(ParseError::TooManyDecimalPlaces);
+        }
+
+        let msats = picos / PICOS_PER_MSAT;
+        if msats > u128::from(MilliSatoshiQuantity::MAX_MONEY.msat()) {
+            return Err(ParseError::ExceedsMaxMoney);
+        }
+
+        Ok(MilliSatoshiQuantity::from_msat(msats as u64))
+    }
+
+    /// Formats the quantity as the shortest BOLT11 amount, e.g. `"20m"` for
+    /// 2 million satoshis. Returns `None` for zero, which cannot be encoded
+    /// (invoices without an amount omit it instead), and for amounts above
+    /// `MAX_MONEY`, which `from_bolt11_amount` would reject.
+    pub fn to_bolt11_amount(self) -> Option<String> {
+        if self > MilliSatoshiQuantity::MAX_MONEY {
+            return None;
+        }
+
+        format_picos(u128::from(self.msat()) * PICOS_PER_MSAT)
+    }
+}
+
+impl BitcoinQuantity {
+    /// Parses a BOLT11 amount like `MilliSatoshiQuantity::from_bolt11_amount`
+    /// does, but also rejects amounts that are not a whole number of satoshis.
+    pub fn from_bolt11_amount(string: &str) -> Result<BitcoinQuantity, ParseError> {
+        MilliSatoshiQuantity::from_bolt11_amount(string)?
+            .to_satoshi_exact()
+            .ok_or(ParseError::TooManyDecimalPlaces)
+    }
+
+    /// Formats the quantity as the shortest BOLT11 amount, e.g. `"2500u"` for
+    /// 250,000 satoshis. Returns `None` for zero and for amounts above
+    /// `MAX_MONEY`.
+    pub fn to_bolt11_amount(self) -> Option<String> {
+        if !self.is_valid_money() {
+            return None;
+        }
+
+        format_picos(u128::from(self.satoshi()) * 1_000 * PICOS_PER_MSAT)
+    }
+}
+
+/// Uses the largest multiplier that divides the amount exactly, which gives
+/// the fewest digits.
+fn format_picos(picos: u128) -> Option<String> {
+    if picos == 0 {
+        return None;
+    }
+
+    MULTIPLIERS
+        .iter()
+        .find(|&&(_, picos_per_unit)| picos.is_multiple_of(picos_per_unit))
+        .map(|&(multiplier, picos_per_unit)| format!("{}{}", picos / picos_per_unit, multiplier))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate spectral;
+
+    use self::spectral::prelude::*;
+    use super::*;
+
+    fn msat(string: &str) -> Result<u64, ParseError> {
+        MilliSatoshiQuantity::from_bolt11_amount(string).map(MilliSatoshiQuantity::msat)
+    }
+
+    #[test]
+    fn parse_bolt11_amounts() {
+        assert_that(&msat("1")).is_ok_containing(100_000_000_000);
+        assert_that(&msat("20m")).is_ok_containing(2_000_000_000);
+        assert_that(&msat("2500u")).is_ok_containing(250_000_000);
+        assert_that(&msat("10n")).is_ok_containing(1_000);
+        assert_that(&msat("2500p")).is_ok_containing(250);
+        assert_that(&msat("10p")).is_ok_containing(1);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_bolt11_amounts() {
+        assert_that(&msat("")).is_err_containing(ParseError::Empty);
+        assert_that(&msat("m")).is_err_containing(ParseError::Empty);
+        assert_that(&msat("2501p")).is_err_containing(ParseError::TooManyDecimalPlaces);
+        assert_that(&msat("025m")).is_err_containing(ParseError::InvalidCharacter('0'));
+        assert_that(&msat("0")).is_err_containing(ParseError::InvalidCharacter('0'));
+        assert_that(&msat("1.5m")).is_err_containing(ParseError::InvalidCharacter('.'));
+        assert_that(&msat("-1m")).is_err_containing(ParseError::InvalidCharacter('-'));
+        assert_that(&msat("10k")).is_err_containing(ParseError::InvalidCharacter('k'));
+        assert_that(&msat("21000001")).is_err_containing(ParseError::ExceedsMaxMoney);
+        assert_that(&msat("99999999999999999999p")).is_err_containing(ParseError::Overflow);
+    }
+
+    #[test]
+    fn format_shortest_bolt11_amount() {
+        let format = |msats| MilliSatoshiQuantity::from_msat(msats).to_bolt11_amount();
+        assert_eq!(format(100_000_000_000), Some("1".to_string()));
+        assert_eq!(format(2_000_000_000), Some("20m".to_string()));
+        assert_eq!(format(250_000_000), Some("2500u".to_string()));
+        assert_eq!(format(1_000), Some("10n".to_string()));
+        assert_eq!(format(250), Some("2500p".to_string()));
+        assert_eq!(format(0), None);
+        assert_eq!(format(2_100_000_000_000_000_001), None);
+    }
+
+    #[test]
+    fn bitcoin_quantity_requires_whole_satoshis() {
+        assert_that(&BitcoinQuantity::from_bolt11_amount("2500u"))
+            .is_ok_containing(BitcoinQuantity::from_satoshi(250_000));
+        assert_that(&BitcoinQuantity::from_bolt11_amount("1n"))
+            .is_err_containing(ParseError::TooManyDecimalPlaces);
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(250_000).to_bolt11_amount(),
+            Some("2500u".to_string())
+        );
+        assert_eq!(
+            BitcoinQuantity::MAX_MONEY.to_bolt11_amount(),
+            Some("21000000".to_string())
+        );
+        assert_eq!(
+            (BitcoinQuantity::MAX_MONEY + BitcoinQuantity::ONE_SAT).to_bolt11_amount(),
+            None
+        );
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(u64::MAX).to_bolt11_amount(),
+            None
+        );
+    }
+
+    #[test]
+    fn bolt11_amount_round_trips() {
+        for &msats in &[1, 10, 999, 1_000, 123_456_789, 2_100_000_000_000_000_000] {
+            let quantity = MilliSatoshiQuantity::from_msat(msats);
+            let encoded = quantity.to_bolt11_amount().unwrap();
+            assert_that(&MilliSatoshiQuantity::from_bolt11_amount(&encoded))
+                .is_ok_containing(quantity);
+        }
+    }
+}
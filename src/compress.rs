@@ -0,0 +1,129 @@
+//! The compact amount encoding Bitcoin Core uses for its UTXO database and
+//! `dumptxoutset` snapshots (`CompressAmount` and `DecompressAmount`).
+
+use {BitcoinQuantity, OutOfRangeError};
+
+impl BitcoinQuantity {
+    /// Compresses the amount exactly like Bitcoin Core's `CompressAmount`.
+    ///
+    /// Trailing decimal zeros are stored as an exponent, which makes round
+    /// amounts encode to small numbers, e.g. 1 BTC to `9` and 50 BTC to `50`.
+    /// Fails for amounts above `MAX_MONEY`, which Core never stores.
+    pub fn compress(self) -> Result<u64, OutOfRangeError> {
+        if !self.is_valid_money() {
+            return Err(OutOfRangeError);
+        }
+
+        let mut n = self.satoshi();
+        if n == 0 {
+            return Ok(0);
+        }
+
+        let mut e = 0;
+        while n.is_multiple_of(10) && e < 9 {
+            n /= 10;
+            e += 1;
+        }
+
+        if e < 9 {
+            let d = n % 10;
+            n /= 10;
+            Ok(1 + (n * 9 + d - 1) * 10 + e)
+        } else {
+            Ok(1 + (n - 1) * 10 + 9)
+        }
+    }
+
+    /// Decompresses an amount exactly like Bitcoin Core's `DecompressAmount`.
+    /// Returns `None` if the amount does not fit into a `u64`; Core would
+    /// silently wrap around instead.
+    pub fn decompress(compressed: u64) -> Option<BitcoinQuantity> {
+        if compressed == 0 {
+            return Some(BitcoinQuantity::ZERO);
+        }
+
+        let x = compressed - 1;
+        let e = x % 10;
+        let x = x / 10;
+        let n = if e < 9 {
+            let d = x % 9 + 1;
+            (x / 9).checked_mul(10)?.checked_add(d)?
+        } else {
+            x + 1
+        };
+
+        (0..e)
+            .try_fold(n, |n, _| n.checked_mul(10))
+            .map(BitcoinQuantity::from_satoshi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {BitcoinQuantity, OutOfRangeError};
+
+    const CENT: u64 = 1_000_000;
+    const COIN: u64 = 100_000_000;
+
+    fn round_trips(sats: u64) -> bool {
+        let quantity = BitcoinQuantity::from_satoshi(sats);
+        BitcoinQuantity::decompress(quantity.compress().unwrap()) == Some(quantity)
+    }
+
+    #[test]
+    fn compress_core_vectors() {
+        for &(sats, compressed) in &[
+            (0, 0x0),
+            (1, 0x1),
+            (CENT, 0x7),
+            (COIN, 0x9),
+            (50 * COIN, 0x32),
+            (21_000_000 * COIN, 0x140_6f40),
+        ] {
+            let quantity = BitcoinQuantity::from_satoshi(sats);
+            assert_eq!(quantity.compress(), Ok(compressed));
+            assert_eq!(BitcoinQuantity::decompress(compressed), Some(quantity));
+        }
+    }
+
+    #[test]
+    fn compress_round_trips_multiples_of_common_units() {
+        for i in 1..=100_000 {
+            assert!(round_trips(i));
+        }
+        for i in 1..=10_000 {
+            assert!(round_trips(i * CENT));
+        }
+        for i in 1..=1_000 {
+            assert!(round_trips(i * COIN));
+        }
+        for i in 1..=100 {
+            assert!(round_trips(i * 50 * COIN));
+        }
+        assert!(round_trips(BitcoinQuantity::MAX_MONEY.satoshi()));
+    }
+
+    #[test]
+    fn decompress_round_trips_small_encodings() {
+        for compressed in 0..100_000 {
+            let quantity = BitcoinQuantity::decompress(compressed).unwrap();
+            assert_eq!(quantity.compress(), Ok(compressed));
+        }
+    }
+
+    #[test]
+    fn compress_rejects_amounts_above_max_money() {
+        let above_max_money = BitcoinQuantity::MAX_MONEY + BitcoinQuantity::ONE_SAT;
+        assert_eq!(above_max_money.compress(), Err(OutOfRangeError));
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(u64::MAX).compress(),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn decompress_rejects_overflowing_amounts() {
+        assert_eq!(BitcoinQuantity::decompress(u64::MAX), None);
+        assert_eq!(BitcoinQuantity::decompress(u64::MAX - 9), None);
+    }
+}
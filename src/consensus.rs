@@ -0,0 +1,159 @@
+//! The encoding of amounts in transaction outputs: a little-endian `int64`.
+
+use std::{error::Error, fmt, io};
+use {BitcoinQuantity, OutOfRangeError};
+
+impl BitcoinQuantity {
+    /// Encodes the amount as 8 byte little-endian `int64`, like the value of
+    /// a transaction output. Fails for amounts above `MAX_MONEY`.
+    pub fn to_consensus_bytes(self) -> Result<[u8; 8], OutOfRangeError> {
+        if !self.is_valid_money() {
+            return Err(OutOfRangeError);
+        }
+
+        Ok((self.satoshi() as i64).to_le_bytes())
+    }
+
+    /// Decodes an 8 byte little-endian `int64`, rejecting negative amounts and
+    /// amounts above `MAX_MONEY`.
+    pub fn from_consensus_bytes(bytes: &[u8]) -> Result<BitcoinQuantity, DecodeError> {
+        if bytes.len() != 8 {
+            return Err(DecodeError::InvalidLength(bytes.len()));
+        }
+
+        let mut array = [0u8; 8];
+        array.copy_from_slice(bytes);
+        let sats = i64::from_le_bytes(array);
+        if sats < 0 {
+            return Err(DecodeError::Negative);
+        }
+
+        BitcoinQuantity::try_from_satoshi(sats as u64).map_err(|_| DecodeError::ExceedsMaxMoney)
+    }
+
+    /// Writes the consensus encoding of the amount to `writer` and returns the
+    /// number of bytes written. Fails with `InvalidInput` for amounts above
+    /// `MAX_MONEY`.
+    pub fn consensus_encode<W>(self, writer: &mut W) -> io::Result<usize>
+    where
+        W: io::Write,
+    {
+        let bytes = self
+            .to_consensus_bytes()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        writer.write_all(&bytes)?;
+
+        Ok(bytes.len())
+    }
+
+    /// Reads a consensus encoded amount from `reader`.
+    pub fn consensus_decode<R>(reader: &mut R) -> Result<BitcoinQuantity, DecodeError>
+    where
+        R: io::Read,
+    {
+        let mut bytes = [0u8; 8];
+        reader.read_exact(&mut bytes).map_err(DecodeError::Io)?;
+
+        BitcoinQuantity::from_consensus_bytes(&bytes)
+    }
+}
+
+/// The reason a consensus encoded amount could not be decoded.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Reading the amount failed.
+    Io(io::Error),
+    /// The input was not exactly 8 bytes long.
+    InvalidLength(usize),
+    /// The encoded `int64` was negative.
+    Negative,
+    /// The amount is larger than the 21 million bitcoin that can ever exist.
+    ExceedsMaxMoney,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            DecodeError::Io(ref e) => write!(f, "failed to read amount: {}", e),
+            DecodeError::InvalidLength(length) => {
+                write!(f, "amount must be 8 bytes long but was {}", length)
+            }
+            DecodeError::Negative => f.write_str("amount cannot be negative"),
+            DecodeError::ExceedsMaxMoney => f.write_str("amount exceeds 21 million bitcoin"),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            DecodeError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_as_little_endian() {
+        let quantity = BitcoinQuantity::from_satoshi(0x0102_0304_0506);
+        assert_eq!(
+            quantity.to_consensus_bytes(),
+            Ok([0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00, 0x00])
+        );
+
+        let mut buffer = Vec::new();
+        assert_eq!(quantity.consensus_encode(&mut buffer).unwrap(), 8);
+        assert_eq!(buffer, [0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn encoding_more_than_max_money_fails() {
+        let quantity = BitcoinQuantity::from_satoshi(u64::MAX);
+        assert_eq!(quantity.to_consensus_bytes(), Err(OutOfRangeError));
+
+        let error = quantity.consensus_encode(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        let mut buffer = Vec::new();
+        for &sats in &[0, 1, 50 * 100_000_000, 2_100_000_000_000_000] {
+            BitcoinQuantity::from_satoshi(sats)
+                .consensus_encode(&mut buffer)
+                .unwrap();
+        }
+
+        let mut reader = &buffer[..];
+        for &sats in &[0, 1, 50 * 100_000_000, 2_100_000_000_000_000] {
+            assert_eq!(
+                BitcoinQuantity::consensus_decode(&mut reader).unwrap(),
+                BitcoinQuantity::from_satoshi(sats)
+            );
+        }
+        match BitcoinQuantity::consensus_decode(&mut reader) {
+            Err(DecodeError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+            result => panic!("expected end of input, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn decode_rejects_invalid_amounts() {
+        match BitcoinQuantity::from_consensus_bytes(&(-1i64).to_le_bytes()) {
+            Err(DecodeError::Negative) => {}
+            result => panic!("expected negative amount error, got {:?}", result),
+        }
+        match BitcoinQuantity::from_consensus_bytes(&2_100_000_000_000_001i64.to_le_bytes()) {
+            Err(DecodeError::ExceedsMaxMoney) => {}
+            result => panic!("expected max money error, got {:?}", result),
+        }
+        match BitcoinQuantity::from_consensus_bytes(&[0; 7]) {
+            Err(DecodeError::InvalidLength(7)) => {}
+            result => panic!("expected invalid length error, got {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::{fmt, str::FromStr};
+use ParseError;
+
+/// A unit in which a bitcoin quantity can be expressed.
+#[derive(PartialEq, Clone, Debug, Copy, Eq, Hash)]
+pub enum Denomination {
+    /// 100,000,000 satoshis.
+    Bitcoin,
+    /// 1,000,000 satoshis.
+    CentiBitcoin,
+    /// 100,000 satoshis.
+    MilliBitcoin,
+    /// 100 satoshis.
+    MicroBitcoin,
+    /// 100 satoshis, the same as `MicroBitcoin` under a friendlier name.
+    Bit,
+    Satoshi,
+    /// One thousandth of a satoshi.
+    MilliSatoshi,
+}
+
+impl Denomination {
+    /// The number of decimal places by which a satoshi amount is shifted to
+    /// express it in this unit. Negative for units smaller than a satoshi.
+    pub fn precision(self) -> i32 {
+        match self {
+            Denomination::Bitcoin => 8,
+            Denomination::CentiBitcoin => 6,
+            Denomination::MilliBitcoin => 5,
+            Denomination::MicroBitcoin | Denomination::Bit => 2,
+            Denomination::Satoshi => 0,
+            Denomination::MilliSatoshi => -3,
+        }
+    }
+}
+
+impl fmt::Display for Denomination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str(match *self {
+            Denomination::Bitcoin => "BTC",
+            Denomination::CentiBitcoin => "cBTC",
+            Denomination::MilliBitcoin => "mBTC",
+            Denomination::MicroBitcoin => "µBTC",
+            Denomination::Bit => "bits",
+            Denomination::Satoshi => "sat",
+            Denomination::MilliSatoshi => "msat",
+        })
+    }
+}
+
+impl FromStr for Denomination {
+    type Err = ParseError;
+
+    /// Parses a unit name case-insensitively. Common aliases such as `sats`,
+    /// `bit`, `uBTC` and `₿` are accepted.
+    fn from_str(string: &str) -> Result<Denomination, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "btc" | "₿" => Ok(Denomination::Bitcoin),
+            "cbtc" => Ok(Denomination::CentiBitcoin),
+            "mbtc" => Ok(Denomination::MilliBitcoin),
+            "µbtc" | "μbtc" | "ubtc" => Ok(Denomination::MicroBitcoin),
+            "bit" | "bits" => Ok(Denomination::Bit),
+            "sat" | "sats" | "satoshi" | "satoshis" => Ok(Denomination::Satoshi),
+            "msat" | "msats" => Ok(Denomination::MilliSatoshi),
+            _ => Err(ParseError::UnknownUnit(string.to_string())),
+        }
+    }
+}
@@ -1,109 +1,583 @@
-extern crate bigdecimal;
+#[cfg(feature = "bitcoin")]
+extern crate bitcoin;
 #[cfg(feature = "serde")]
 extern crate serde;
+#[cfg(feature = "rpc")]
+extern crate serde_json;
+
+#[macro_use]
+mod macros;
+
+mod bitcoin_core;
+mod bolt11;
+mod compress;
+mod consensus;
+mod denomination;
+mod millisatoshi;
+mod rounding;
+#[cfg(feature = "bitcoin")]
+mod rust_bitcoin;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+mod signed;
+mod split;
+
+pub use consensus::DecodeError;
+pub use denomination::Denomination;
+pub use millisatoshi::MilliSatoshiQuantity;
+pub use rounding::RoundingMode;
+pub use signed::SignedBitcoinQuantity;
 
-use bigdecimal::ParseBigDecimalError;
 #[cfg(feature = "serde")]
 use serde::{
     de::{self, Deserialize, Deserializer},
     ser::{Serialize, Serializer},
 };
 use std::{
+    convert::TryFrom,
+    error::Error,
     fmt,
-    ops::{Add, Sub},
+    iter::Sum,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign},
     str::FromStr,
 };
 
+const SATS_PER_BITCOIN: u64 = 100_000_000;
+
 #[derive(PartialEq, Clone, Debug, Copy, PartialOrd, Ord, Eq, Hash)]
 pub struct BitcoinQuantity(u64);
 
 impl BitcoinQuantity {
+    pub const ZERO: BitcoinQuantity = BitcoinQuantity(0);
+    pub const ONE_SAT: BitcoinQuantity = BitcoinQuantity(1);
+    pub const ONE_BTC: BitcoinQuantity = BitcoinQuantity(SATS_PER_BITCOIN);
+    /// The largest amount that is valid according to consensus: 21 million
+    /// bitcoin.
+    pub const MAX_MONEY: BitcoinQuantity = BitcoinQuantity(21_000_000 * SATS_PER_BITCOIN);
+
     pub fn from_satoshi(sats: u64) -> Self {
         BitcoinQuantity(sats)
     }
+    /// Like `from_satoshi` but rejects amounts above `MAX_MONEY`.
+    pub fn try_from_satoshi(sats: u64) -> Result<Self, OutOfRangeError> {
+        let quantity = BitcoinQuantity(sats);
+        if quantity.is_valid_money() {
+            Ok(quantity)
+        } else {
+            Err(OutOfRangeError)
+        }
+    }
+    /// Rounds `btc` to the nearest satoshi. Negative and NaN inputs become
+    /// zero and infinity saturates; use `try_from_bitcoin` to reject those.
     pub fn from_bitcoin(btc: f64) -> Self {
         BitcoinQuantity((btc * 100_000_000.0).round() as u64)
     }
+    /// Converts a floating point bitcoin amount, rejecting values that are not
+    /// finite, negative, above `MAX_MONEY` or that are not the closest `f64`
+    /// to a whole number of satoshis.
+    pub fn try_from_bitcoin(btc: f64) -> Result<Self, FromBitcoinError> {
+        if !btc.is_finite() {
+            return Err(FromBitcoinError::NotFinite);
+        }
+        if btc < 0.0 {
+            return Err(FromBitcoinError::Negative);
+        }
+        if btc > BitcoinQuantity::MAX_MONEY.bitcoin() {
+            return Err(FromBitcoinError::ExceedsMaxMoney);
+        }
+
+        let quantity = BitcoinQuantity::from_bitcoin(btc);
+        if quantity.bitcoin() != btc {
+            return Err(FromBitcoinError::TooPrecise);
+        }
+
+        Ok(quantity)
+    }
     pub fn satoshi(self) -> u64 {
         self.0
     }
     pub fn bitcoin(self) -> f64 {
         (self.0 as f64) / 100_000_000.0
     }
-}
+    /// Creates a quantity from a whole number of `denomination` units. Returns
+    /// `None` if the result does not fit into a `u64` of satoshis or, for
+    /// units smaller than a satoshi, is not a whole number of satoshis.
+    pub fn from_denomination(value: u64, denomination: Denomination) -> Option<Self> {
+        let precision = denomination.precision();
+        let factor = 10u64.pow(precision.unsigned_abs());
+        if precision >= 0 {
+            value.checked_mul(factor).map(BitcoinQuantity)
+        } else if value.is_multiple_of(factor) {
+            Some(BitcoinQuantity(value / factor))
+        } else {
+            None
+        }
+    }
+    /// Expresses the quantity as a whole number of `denomination` units.
+    /// Returns `None` if it is not a whole number of those units or does not
+    /// fit into a `u64`.
+    pub fn to_denomination(self, denomination: Denomination) -> Option<u64> {
+        let precision = denomination.precision();
+        let factor = 10u64.pow(precision.unsigned_abs());
+        if precision < 0 {
+            self.0.checked_mul(factor)
+        } else if self.0.is_multiple_of(factor) {
+            Some(self.0 / factor)
+        } else {
+            None
+        }
+    }
+    /// Renders the quantity exactly in `denomination`, without the unit and
+    /// with trailing zeros in the fractional part trimmed.
+    pub fn to_string_in(self, denomination: Denomination) -> String {
+        let precision = denomination.precision();
+        if precision <= 0 {
+            return (u128::from(self.0) * 10u128.pow(precision.unsigned_abs())).to_string();
+        }
 
-impl Add for BitcoinQuantity {
-    type Output = BitcoinQuantity;
+        trimmed_decimal(self.0, precision as usize)
+    }
+    /// Parses a decimal amount without unit, expressed in `denomination`.
+    pub fn from_str_in(string: &str, denomination: Denomination) -> Result<Self, ParseError> {
+        let sats = parse_decimal(string, denomination.precision())?;
+        Ok(BitcoinQuantity::try_from_satoshi(sats)?)
+    }
+    /// Whether the amount is within `0..=MAX_MONEY`, mirroring Bitcoin Core's
+    /// `MoneyRange`.
+    pub fn is_valid_money(self) -> bool {
+        self <= BitcoinQuantity::MAX_MONEY
+    }
+
+    /// Subtracts `rhs` from `self`, returning a negative quantity if `rhs` is
+    /// the larger one. Returns `None` if the difference does not fit into a
+    /// `SignedBitcoinQuantity`.
+    pub fn checked_signed_sub(self, rhs: BitcoinQuantity) -> Option<SignedBitcoinQuantity> {
+        let lhs = SignedBitcoinQuantity::try_from(self).ok()?;
+        let rhs = SignedBitcoinQuantity::try_from(rhs).ok()?;
+        lhs.checked_sub(rhs)
+    }
 
-    fn add(self, rhs: BitcoinQuantity) -> BitcoinQuantity {
-        BitcoinQuantity(self.0 + rhs.0)
+    /// Like `checked_signed_sub` but panics if the difference cannot be
+    /// represented, following the operator overflow policy.
+    pub fn signed_sub(self, rhs: BitcoinQuantity) -> SignedBitcoinQuantity {
+        self.checked_signed_sub(rhs)
+            .expect("overflow when subtracting bitcoin quantities")
     }
 }
 
-impl Sub for BitcoinQuantity {
-    type Output = BitcoinQuantity;
+impl_unsigned_amount_ops!(BitcoinQuantity, "bitcoin");
+
+/// Sums up an iterator of quantities, returning `None` instead of panicking
+/// if the total overflows.
+pub trait CheckedSum<Item> {
+    /// The type of the total.
+    type Output;
+
+    fn checked_sum(self) -> Option<Self::Output>;
+}
 
-    fn sub(self, rhs: BitcoinQuantity) -> BitcoinQuantity {
-        BitcoinQuantity(self.0 - rhs.0)
+impl TryFrom<f64> for BitcoinQuantity {
+    type Error = FromBitcoinError;
+
+    fn try_from(btc: f64) -> Result<BitcoinQuantity, FromBitcoinError> {
+        BitcoinQuantity::try_from_bitcoin(btc)
     }
 }
 
+/// Renders the quantity in bitcoin with trailing zeros trimmed, e.g.
+/// `"1234.000001 BTC"`.
+///
+/// - A precision (`{:.8}`) renders exactly that many decimal places. Fewer
+///   than 8 places round half up, i.e. half a unit in the last place is
+///   rounded away from zero.
+/// - The alternate flag (`{:#}`) renders the quantity in satoshis instead,
+///   e.g. `"150 sat"`, ignoring any precision.
+/// - Width, fill, alignment and the `+` flag apply to the whole rendering,
+///   unit included.
 impl fmt::Display for BitcoinQuantity {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{} BTC", self.bitcoin())
+        f.pad_integral(true, "", &self.render(f))
+    }
+}
+
+impl BitcoinQuantity {
+    /// The unsigned rendering used by `Display`, before padding and sign are
+    /// applied.
+    pub(crate) fn render(self, f: &fmt::Formatter) -> String {
+        if f.alternate() {
+            return format!("{} {}", self.0, Denomination::Satoshi);
+        }
+
+        let number = match f.precision() {
+            None => self.to_string_in(Denomination::Bitcoin),
+            Some(places) => self.to_fixed_bitcoin(places),
+        };
+        format!("{} {}", number, Denomination::Bitcoin)
+    }
+
+    fn to_fixed_bitcoin(self, places: usize) -> String {
+        fixed_decimal(self.0, Denomination::Bitcoin.precision() as usize, places)
     }
 }
 
+/// Renders `units` with `exact_places` implied decimal places, with trailing
+/// zeros in the fractional part trimmed.
+fn trimmed_decimal(units: u64, exact_places: usize) -> String {
+    let factor = 10u64.pow(exact_places as u32);
+    let (integer, fraction) = (units / factor, units % factor);
+    if fraction == 0 {
+        return integer.to_string();
+    }
+
+    let fraction = format!("{:0width$}", fraction, width = exact_places);
+    format!("{}.{}", integer, fraction.trim_end_matches('0'))
+}
+
+/// Renders `units` with `exact_places` implied decimal places using exactly
+/// `places` decimal places, padding with zeros or rounding half up.
+fn fixed_decimal(units: u64, exact_places: usize, places: usize) -> String {
+    if places >= exact_places {
+        let factor = 10u64.pow(exact_places as u32);
+        return format!(
+            "{}.{:0exact$}{:0<padding$}",
+            units / factor,
+            units % factor,
+            "",
+            exact = exact_places,
+            padding = places - exact_places
+        );
+    }
+
+    let factor = 10u128.pow((exact_places - places) as u32);
+    let rounded = (u128::from(units) + factor / 2) / factor;
+    if places == 0 {
+        return rounded.to_string();
+    }
+
+    let scale = 10u128.pow(places as u32);
+    format!(
+        "{}.{:0places$}",
+        rounded / scale,
+        rounded % scale,
+        places = places
+    )
+}
+
 impl FromStr for BitcoinQuantity {
-    type Err = ParseBigDecimalError;
+    type Err = ParseError;
 
+    /// Parses a decimal amount such as `"0.1"`, `"20999999.99999999 BTC"`,
+    /// `"150 sat"` or `"₿1.5"` exactly, without going through floating point.
+    /// The unit is optional, case-insensitive and defaults to bitcoin, and
+    /// trailing whitespace is ignored. The output of `Display` is accepted for
+    /// every amount up to `MAX_MONEY`. Trailing zeros beyond the precision of
+    /// the unit are ignored, but inputs with non-zero digits beyond it, a sign
+    /// or a value above `MAX_MONEY` are rejected rather than rounded.
     fn from_str(string: &str) -> Result<BitcoinQuantity, Self::Err> {
-        let dec = string.parse()?;
-        Ok(Self::from_bitcoin(dec))
+        let (number, denomination) = split_unit(string)?;
+        BitcoinQuantity::from_str_in(number, denomination)
+    }
+}
+
+/// Splits an amount into its number and its unit, which is either a suffix
+/// (optionally separated by whitespace) or a `₿` prefix. Trailing whitespace
+/// is ignored.
+fn split_unit(string: &str) -> Result<(&str, Denomination), ParseError> {
+    let string = string.trim_end();
+    if string.starts_with('₿') {
+        let number = string['₿'.len_utf8()..].trim_start();
+        return match number.find(|c: char| !is_number_char(c)) {
+            Some(_) => Err(ParseError::InvalidCharacter('₿')),
+            None => Ok((number, Denomination::Bitcoin)),
+        };
+    }
+
+    let number_end = match string.find(|c: char| !is_number_char(c)) {
+        Some(index) => index,
+        None => return Ok((string, Denomination::Bitcoin)),
+    };
+    let (number, rest) = string.split_at(number_end);
+    let unit = rest.trim_start();
+
+    if number.is_empty() {
+        let c = rest.chars().next().expect("rest is not empty");
+        return Err(ParseError::InvalidCharacter(c));
+    }
+    if let Some(index) = unit.find(|c: char| c.is_ascii_digit()) {
+        // Blame the digit if it trails a valid unit as in `"1 BTC2"`, but not
+        // if the unit is bogus anyway as in `"1e8"`.
+        let c = match unit[..index].trim_end().parse::<Denomination>() {
+            Ok(_) => unit[index..].chars().next(),
+            Err(_) => unit.chars().next(),
+        };
+        return Err(ParseError::InvalidCharacter(c.expect("unit is not empty")));
+    }
+
+    Ok((number, unit.parse()?))
+}
+
+fn is_number_char(c: char) -> bool {
+    c.is_ascii_digit() || c == '.' || c == '-' || c == '+'
+}
+
+/// Parses a decimal number into a whole number of units that have `precision`
+/// decimal places relative to the unit the number is expressed in.
+fn parse_decimal(string: &str, precision: i32) -> Result<u64, ParseError> {
+    if string.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    if string.starts_with('-') {
+        return Err(ParseError::Negative);
+    }
+
+    let (integer, fraction) = match string.find('.') {
+        Some(index) => (&string[..index], &string[index + 1..]),
+        None => (string, ""),
+    };
+
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(ParseError::InvalidCharacter('.'));
+    }
+    if let Some(c) = integer
+        .chars()
+        .chain(fraction.chars())
+        .find(|c| !c.is_ascii_digit())
+    {
+        return Err(ParseError::InvalidCharacter(c));
+    }
+
+    let places = precision.max(0) as usize;
+    if fraction.len() > places && fraction[places..].bytes().any(|digit| digit != b'0') {
+        return Err(ParseError::TooManyDecimalPlaces);
+    }
+
+    let whole = parse_digits(integer)?;
+    let fraction = fraction
+        .bytes()
+        .chain(std::iter::repeat(b'0'))
+        .take(places)
+        .fold(0u64, |acc, digit| acc * 10 + u64::from(digit - b'0'));
+
+    if precision < 0 {
+        let factor = 10u64.pow(precision.unsigned_abs());
+        return if whole.is_multiple_of(factor) {
+            Ok(whole / factor)
+        } else {
+            Err(ParseError::TooManyDecimalPlaces)
+        };
     }
+
+    whole
+        .checked_mul(10u64.pow(places as u32))
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or(ParseError::Overflow)
 }
 
 #[cfg(feature = "serde")]
-impl<'de> Deserialize<'de> for BitcoinQuantity {
-    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+fn parse_satoshi(string: &str) -> Result<u64, ParseError> {
+    if string.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    if string.starts_with('-') {
+        return Err(ParseError::Negative);
+    }
+    if let Some(c) = string.chars().find(|c| !c.is_ascii_digit()) {
+        return Err(ParseError::InvalidCharacter(c));
+    }
+
+    parse_digits(string)
+}
+
+fn parse_digits(digits: &str) -> Result<u64, ParseError> {
+    digits.bytes().try_fold(0u64, |acc, digit| {
+        acc.checked_mul(10)
+            .and_then(|acc| acc.checked_add(u64::from(digit - b'0')))
+            .ok_or(ParseError::Overflow)
+    })
+}
+
+/// The amount is outside of the range accepted by the target type, e.g. it is
+/// larger than `BitcoinQuantity::MAX_MONEY` or negative where only positive
+/// amounts are allowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfRangeError;
+
+impl fmt::Display for OutOfRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str("amount is out of range")
+    }
+}
+
+impl Error for OutOfRangeError {}
+
+/// The reason a floating point amount could not be converted into a bitcoin
+/// quantity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FromBitcoinError {
+    /// The amount was NaN or infinite.
+    NotFinite,
+    /// The amount was below zero.
+    Negative,
+    /// The amount has a fractional satoshi part.
+    TooPrecise,
+    /// The amount is larger than the 21 million bitcoin that can ever exist.
+    ExceedsMaxMoney,
+}
+
+impl fmt::Display for FromBitcoinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            FromBitcoinError::NotFinite => f.write_str("amount is not a finite number"),
+            FromBitcoinError::Negative => f.write_str("amount cannot be negative"),
+            FromBitcoinError::TooPrecise => f.write_str("amount is not a whole number of satoshis"),
+            FromBitcoinError::ExceedsMaxMoney => f.write_str("amount exceeds 21 million bitcoin"),
+        }
+    }
+}
+
+impl Error for FromBitcoinError {}
+
+/// The reason a string could not be parsed into a bitcoin quantity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input was empty.
+    Empty,
+    /// The input contained a character that is not part of an amount.
+    InvalidCharacter(char),
+    /// The input had non-zero digits beyond the decimal places the unit
+    /// supports.
+    TooManyDecimalPlaces,
+    /// The input had a minus sign.
+    Negative,
+    /// The amount is larger than the 21 million bitcoin that can ever exist.
+    ExceedsMaxMoney,
+    /// The amount does not fit into a `u64` of satoshis.
+    Overflow,
+    /// The input ended in a unit that is not known.
+    UnknownUnit(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ParseError::Empty => f.write_str("amount is empty"),
+            ParseError::InvalidCharacter(c) => write!(f, "invalid character '{}' in amount", c),
+            ParseError::TooManyDecimalPlaces => f.write_str("amount has too many decimal places"),
+            ParseError::Negative => f.write_str("amount cannot be negative"),
+            ParseError::ExceedsMaxMoney => f.write_str("amount exceeds 21 million bitcoin"),
+            ParseError::Overflow => f.write_str("amount is too large to be represented"),
+            ParseError::UnknownUnit(ref unit) => write!(f, "unknown unit '{}'", unit),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<OutOfRangeError> for ParseError {
+    fn from(_: OutOfRangeError) -> ParseError {
+        ParseError::ExceedsMaxMoney
+    }
+}
+
+/// Accepts a satoshi count as an integer or a string. If `accept_unit` is set,
+/// strings with a unit such as `"1.5 BTC"` or `"150 sat"` are accepted as well.
+#[cfg(feature = "serde")]
+pub(crate) struct SatoshiVisitor {
+    pub(crate) accept_unit: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> de::Visitor<'de> for SatoshiVisitor {
+    type Value = BitcoinQuantity;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.accept_unit {
+            formatter.write_str("A satoshi quantity or a string representing an amount with unit")
+        } else {
+            formatter.write_str("A string or integer representing a satoshi quantity")
+        }
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<BitcoinQuantity, E>
     where
-        D: Deserializer<'de>,
+        E: de::Error,
     {
-        struct Visitor;
-
-        impl<'vde> de::Visitor<'vde> for Visitor {
-            type Value = BitcoinQuantity;
+        BitcoinQuantity::try_from_satoshi(v).map_err(|e| E::custom(ParseError::from(e)))
+    }
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-                formatter.write_str("A string representing a satoshi quantity")
-            }
+    fn visit_i64<E>(self, v: i64) -> Result<BitcoinQuantity, E>
+    where
+        E: de::Error,
+    {
+        if v < 0 {
+            return Err(E::custom(ParseError::Negative));
+        }
+        self.visit_u64(v as u64)
+    }
 
-            fn visit_str<E>(self, v: &str) -> Result<BitcoinQuantity, E>
-            where
-                E: de::Error,
-            {
-                Ok(v.parse()
-                    .map(BitcoinQuantity::from_satoshi)
-                    .map_err(E::custom)?)
-            }
+    fn visit_str<E>(self, v: &str) -> Result<BitcoinQuantity, E>
+    where
+        E: de::Error,
+    {
+        if self.accept_unit && v.contains(|c: char| !is_number_char(c)) {
+            return v.parse().map_err(E::custom);
         }
 
-        deserializer.deserialize_str(Visitor)
+        let sats = parse_satoshi(v).map_err(E::custom)?;
+        self.visit_u64(sats)
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<BitcoinQuantity, E>
+    where
+        E: de::Error,
+    {
+        self.visit_str(v)
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<BitcoinQuantity, E>
+    where
+        E: de::Error,
+    {
+        self.visit_str(&v)
     }
 }
 
+/// Accepts the number of satoshis as a string, e.g. `"100000000"`, or as an
+/// integer, e.g. `100000000`. Formats that are not human readable always
+/// carry a plain `u64`.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for BitcoinQuantity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(SatoshiVisitor { accept_unit: false })
+        } else {
+            deserializer.deserialize_u64(SatoshiVisitor { accept_unit: false })
+        }
+    }
+}
+
+/// Serializes the number of satoshis as a string in human readable formats
+/// such as JSON and as a plain `u64` in binary formats.
 #[cfg(feature = "serde")]
 impl Serialize for BitcoinQuantity {
     fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.0.to_string().as_str())
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.0.to_string().as_str())
+        } else {
+            serializer.serialize_u64(self.0)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate bincode;
     extern crate serde_json;
     extern crate spectral;
 
@@ -112,17 +586,17 @@ mod tests {
 
     #[test]
     fn hundred_million_sats_is_a_bitcoin() {
-        assert_that(&BitcoinQuantity::from_satoshi(100_000_000).bitcoin()).is_equal_to(&1.0);
+        assert_that(&BitcoinQuantity::from_satoshi(100_000_000).bitcoin()).is_equal_to(1.0);
     }
 
     #[test]
     fn a_bitcoin_is_a_hundred_million_sats() {
-        assert_that(&BitcoinQuantity::from_bitcoin(1.0).satoshi()).is_equal_to(&100_000_000);
+        assert_that(&BitcoinQuantity::from_bitcoin(1.0).satoshi()).is_equal_to(100_000_000);
     }
     #[test]
     fn a_bitcoin_as_string_is_a_hundred_million_sats() {
         assert_that(&BitcoinQuantity::from_str("1.00000001").unwrap())
-            .is_equal_to(&BitcoinQuantity::from_bitcoin(1.000_000_01));
+            .is_equal_to(BitcoinQuantity::from_bitcoin(1.000_000_01));
     }
 
     #[test]
@@ -164,8 +638,527 @@ mod tests {
         assert_eq!(quantity, BitcoinQuantity::from_satoshi(100_000_000))
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_negative_bitcoin_quantity_fails() {
+        let error = serde_json::from_str::<BitcoinQuantity>("\"-1\"").unwrap_err();
+        assert_that(&error.to_string()).contains("amount cannot be negative");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_bitcoin_quantity_from_integer() {
+        let quantity = serde_json::from_str::<BitcoinQuantity>("100000000").unwrap();
+        assert_eq!(quantity, BitcoinQuantity::from_satoshi(100_000_000))
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_negative_integer_fails() {
+        let error = serde_json::from_str::<BitcoinQuantity>("-1").unwrap_err();
+        assert_that(&error.to_string()).contains("amount cannot be negative");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_bitcoin_quantity_rejects_unit_by_default() {
+        let error = serde_json::from_str::<BitcoinQuantity>("\"1 BTC\"").unwrap_err();
+        assert_that(&error.to_string()).contains("invalid character ' ' in amount");
+        assert!(serde_json::from_str::<BitcoinQuantity>("1.5").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_more_than_max_money_fails() {
+        let error = serde_json::from_str::<BitcoinQuantity>("\"2100000000000001\"").unwrap_err();
+        assert_that(&error.to_string()).contains("amount exceeds 21 million bitcoin");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bincode_round_trip_uses_plain_u64() {
+        let quantity = BitcoinQuantity::from_satoshi(100_000_000);
+        let bytes = bincode::serialize(&quantity).unwrap();
+        assert_eq!(bytes, 100_000_000u64.to_le_bytes());
+        assert_eq!(
+            bincode::deserialize::<BitcoinQuantity>(&bytes).unwrap(),
+            quantity
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bincode_rejects_more_than_max_money() {
+        let bytes = bincode::serialize(&2_100_000_000_000_001u64).unwrap();
+        assert!(bincode::deserialize::<BitcoinQuantity>(&bytes).is_err());
+    }
+
     #[test]
     fn bitcoin_with_more_than_seven_decimal_places_is_truncated() {
-        assert_that(&BitcoinQuantity::from_bitcoin(0.000000495).satoshi()).is_equal_to(&50);
+        assert_that(&BitcoinQuantity::from_bitcoin(0.000000495).satoshi()).is_equal_to(50);
+    }
+
+    #[test]
+    fn checked_sub_below_zero_is_none() {
+        let one = BitcoinQuantity::from_satoshi(1);
+        let two = BitcoinQuantity::from_satoshi(2);
+        assert_that(&one.checked_sub(two)).is_none();
+        assert_that(&two.checked_sub(one)).is_equal_to(Some(one));
+    }
+
+    #[test]
+    fn checked_add_overflow_is_none() {
+        let max = BitcoinQuantity::from_satoshi(u64::MAX);
+        assert_that(&max.checked_add(BitcoinQuantity::from_satoshi(1))).is_none();
+    }
+
+    #[test]
+    fn saturating_arithmetic_clamps() {
+        let one = BitcoinQuantity::from_satoshi(1);
+        let max = BitcoinQuantity::from_satoshi(u64::MAX);
+        assert_eq!(one.saturating_sub(max), BitcoinQuantity::from_satoshi(0));
+        assert_eq!(max.saturating_add(one), max);
+    }
+
+    #[test]
+    fn overflowing_arithmetic_reports_overflow() {
+        let zero = BitcoinQuantity::from_satoshi(0);
+        let one = BitcoinQuantity::from_satoshi(1);
+        assert_eq!(
+            zero.overflowing_sub(one),
+            (BitcoinQuantity::from_satoshi(u64::MAX), true)
+        );
+        assert_eq!(
+            one.overflowing_add(one),
+            (BitcoinQuantity::from_satoshi(2), false)
+        );
+    }
+
+    #[test]
+    fn multiply_and_divide_by_scalars() {
+        let quantity = BitcoinQuantity::from_satoshi(1_001);
+        assert_eq!(quantity * 3, BitcoinQuantity::from_satoshi(3_003));
+        assert_eq!(3 * quantity, BitcoinQuantity::from_satoshi(3_003));
+        assert_eq!(quantity / 2, BitcoinQuantity::from_satoshi(500));
+        assert_eq!(quantity % 2, BitcoinQuantity::ONE_SAT);
+        assert_eq!(quantity.checked_div(0), None);
+        assert_eq!(quantity.checked_rem(0), None);
+        assert_eq!(quantity.checked_mul(u64::MAX), None);
+        assert_eq!(
+            quantity.saturating_mul(u64::MAX),
+            BitcoinQuantity::from_satoshi(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn divide_by_quantity() {
+        let balance = BitcoinQuantity::from_satoshi(1_050);
+        let output = BitcoinQuantity::from_satoshi(100);
+        assert_eq!(balance / output, 10.5);
+        assert_eq!(BitcoinQuantity::from_satoshi(25) / output, 0.25);
+        assert_eq!(balance % output, BitcoinQuantity::from_satoshi(50));
+    }
+
+    #[test]
+    #[should_panic]
+    fn dividing_by_zero_quantity_panics() {
+        let _ = BitcoinQuantity::ONE_BTC / BitcoinQuantity::ZERO;
+    }
+
+    #[test]
+    #[should_panic]
+    fn dividing_by_zero_panics() {
+        let _ = BitcoinQuantity::ONE_BTC / 0;
+    }
+
+    #[test]
+    #[should_panic]
+    fn multiplying_past_u64_panics() {
+        let _ = BitcoinQuantity::from_satoshi(u64::MAX) * 2;
+    }
+
+    #[test]
+    fn sum_quantities() {
+        let utxos = vec![
+            BitcoinQuantity::from_satoshi(1),
+            BitcoinQuantity::from_satoshi(20),
+            BitcoinQuantity::from_satoshi(300),
+        ];
+        assert_eq!(
+            utxos.iter().sum::<BitcoinQuantity>(),
+            BitcoinQuantity::from_satoshi(321)
+        );
+        assert_eq!(
+            utxos.into_iter().sum::<BitcoinQuantity>(),
+            BitcoinQuantity::from_satoshi(321)
+        );
+        assert_eq!(
+            Vec::<BitcoinQuantity>::new()
+                .iter()
+                .sum::<BitcoinQuantity>(),
+            BitcoinQuantity::ZERO
+        );
+    }
+
+    #[test]
+    fn checked_sum_quantities() {
+        let utxos = vec![BitcoinQuantity::ONE_BTC, BitcoinQuantity::ONE_SAT];
+        assert_eq!(
+            utxos.iter().checked_sum(),
+            Some(BitcoinQuantity::from_satoshi(100_000_001))
+        );
+        assert_eq!(
+            utxos.into_iter().checked_sum(),
+            Some(BitcoinQuantity::from_satoshi(100_000_001))
+        );
+
+        let overflowing = [
+            BitcoinQuantity::from_satoshi(u64::MAX),
+            BitcoinQuantity::ONE_SAT,
+        ];
+        assert_eq!(overflowing.iter().checked_sum(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sum_overflow_panics() {
+        let overflowing = [
+            BitcoinQuantity::from_satoshi(u64::MAX),
+            BitcoinQuantity::ONE_SAT,
+        ];
+        let _ = overflowing.iter().sum::<BitcoinQuantity>();
+    }
+
+    #[test]
+    fn assign_operators() {
+        let mut balance = BitcoinQuantity::from_satoshi(1_000);
+        let fee = BitcoinQuantity::from_satoshi(100);
+
+        balance -= fee;
+        balance += &fee;
+        balance -= &fee;
+        assert_eq!(balance, BitcoinQuantity::from_satoshi(900));
+
+        balance *= 3;
+        balance /= &2;
+        balance %= 1_000;
+        assert_eq!(balance, BitcoinQuantity::from_satoshi(350));
+
+        balance %= fee;
+        assert_eq!(balance, BitcoinQuantity::from_satoshi(50));
+    }
+
+    #[test]
+    fn reference_operands() {
+        let (a, b) = (
+            BitcoinQuantity::from_satoshi(300),
+            BitcoinQuantity::from_satoshi(100),
+        );
+        let (a_ref, b_ref) = (&a, &b);
+
+        assert_eq!(a_ref + b_ref, BitcoinQuantity::from_satoshi(400));
+        assert_eq!(a + b_ref, BitcoinQuantity::from_satoshi(400));
+        assert_eq!(a_ref - b, BitcoinQuantity::from_satoshi(200));
+        assert_eq!(a_ref * 2, BitcoinQuantity::from_satoshi(600));
+        assert_eq!(2 * a_ref, BitcoinQuantity::from_satoshi(600));
+        assert_eq!(a_ref / b_ref, 3.0);
+        assert_eq!(a_ref % 7, BitcoinQuantity::from_satoshi(6));
+    }
+
+    #[test]
+    #[should_panic]
+    fn subtract_assign_below_zero_panics() {
+        let mut balance = BitcoinQuantity::ONE_SAT;
+        balance -= BitcoinQuantity::ONE_BTC;
+    }
+
+    #[test]
+    #[should_panic]
+    fn subtracting_below_zero_panics() {
+        let _ = BitcoinQuantity::from_satoshi(1) - BitcoinQuantity::from_satoshi(2);
+    }
+
+    #[test]
+    fn parse_bitcoin_is_exact() {
+        assert_eq!(
+            BitcoinQuantity::from_str("0.1").unwrap().satoshi(),
+            10_000_000
+        );
+        assert_eq!(
+            BitcoinQuantity::from_str("20999999.99999999")
+                .unwrap()
+                .satoshi(),
+            2_099_999_999_999_999
+        );
+        assert_eq!(
+            BitcoinQuantity::from_str(".5").unwrap().satoshi(),
+            50_000_000
+        );
+        assert_eq!(
+            BitcoinQuantity::from_str("1.2345678900").unwrap().satoshi(),
+            123_456_789
+        );
+    }
+
+    #[test]
+    fn parse_bitcoin_rejects_invalid_input() {
+        for &(input, ref error) in &[
+            ("", ParseError::Empty),
+            (".", ParseError::InvalidCharacter('.')),
+            ("-1", ParseError::Negative),
+            ("+1", ParseError::InvalidCharacter('+')),
+            ("1.000000001", ParseError::TooManyDecimalPlaces),
+            ("1.0000000010", ParseError::TooManyDecimalPlaces),
+            ("1.2.3", ParseError::InvalidCharacter('.')),
+            (" 1", ParseError::InvalidCharacter(' ')),
+            ("1e8", ParseError::InvalidCharacter('e')),
+            ("21000000.00000001", ParseError::ExceedsMaxMoney),
+            ("184467440737.09551615", ParseError::ExceedsMaxMoney),
+            ("184467440737.09551616", ParseError::Overflow),
+        ] {
+            assert_that(&BitcoinQuantity::from_str(input)).is_err_containing(error);
+        }
+    }
+
+    #[test]
+    fn max_money_is_valid_money() {
+        assert!(BitcoinQuantity::MAX_MONEY.is_valid_money());
+        assert!(!(BitcoinQuantity::MAX_MONEY + BitcoinQuantity::ONE_SAT).is_valid_money());
+        assert_eq!(
+            BitcoinQuantity::from_str("21000000").unwrap(),
+            BitcoinQuantity::MAX_MONEY
+        );
+    }
+
+    #[test]
+    fn try_from_satoshi_rejects_more_than_max_money() {
+        assert_that(&BitcoinQuantity::try_from_satoshi(2_100_000_000_000_000))
+            .is_ok_containing(BitcoinQuantity::MAX_MONEY);
+        assert_that(&BitcoinQuantity::try_from_satoshi(2_100_000_000_000_001))
+            .is_err_containing(OutOfRangeError);
+    }
+
+    #[test]
+    fn try_from_bitcoin_accepts_whole_satoshis() {
+        assert_that(&BitcoinQuantity::try_from_bitcoin(0.000_123_45))
+            .is_ok_containing(BitcoinQuantity::from_satoshi(12_345));
+        assert_that(&BitcoinQuantity::try_from_bitcoin(20_999_999.999_999_99))
+            .is_ok_containing(BitcoinQuantity::from_satoshi(2_099_999_999_999_999));
+        assert_that(&BitcoinQuantity::try_from(21_000_000.0))
+            .is_ok_containing(BitcoinQuantity::MAX_MONEY);
+    }
+
+    #[test]
+    fn try_from_bitcoin_rejects_garbage() {
+        assert_that(&BitcoinQuantity::try_from_bitcoin(f64::NAN))
+            .is_err_containing(FromBitcoinError::NotFinite);
+        assert_that(&BitcoinQuantity::try_from_bitcoin(f64::INFINITY))
+            .is_err_containing(FromBitcoinError::NotFinite);
+        assert_that(&BitcoinQuantity::try_from_bitcoin(-1.0))
+            .is_err_containing(FromBitcoinError::Negative);
+        assert_that(&BitcoinQuantity::try_from_bitcoin(0.000_000_495))
+            .is_err_containing(FromBitcoinError::TooPrecise);
+        assert_that(&BitcoinQuantity::try_from_bitcoin(21_000_000.000_000_01))
+            .is_err_containing(FromBitcoinError::ExceedsMaxMoney);
+    }
+
+    #[test]
+    fn convert_from_denomination() {
+        assert_eq!(
+            BitcoinQuantity::from_denomination(3, Denomination::MilliBitcoin),
+            Some(BitcoinQuantity::from_satoshi(300_000))
+        );
+        assert_eq!(
+            BitcoinQuantity::from_denomination(25, Denomination::Bit),
+            Some(BitcoinQuantity::from_satoshi(2_500))
+        );
+        assert_eq!(
+            BitcoinQuantity::from_denomination(1_000, Denomination::MilliSatoshi),
+            Some(BitcoinQuantity::ONE_SAT)
+        );
+        assert_eq!(
+            BitcoinQuantity::from_denomination(1_001, Denomination::MilliSatoshi),
+            None
+        );
+        assert_eq!(
+            BitcoinQuantity::from_denomination(u64::MAX, Denomination::CentiBitcoin),
+            None
+        );
+    }
+
+    #[test]
+    fn convert_to_denomination() {
+        let quantity = BitcoinQuantity::from_satoshi(1_500_000);
+        assert_eq!(quantity.to_denomination(Denomination::CentiBitcoin), None);
+        assert_eq!(
+            quantity.to_denomination(Denomination::MilliBitcoin),
+            Some(15)
+        );
+        assert_eq!(
+            quantity.to_denomination(Denomination::MicroBitcoin),
+            Some(15_000)
+        );
+        assert_eq!(
+            quantity.to_denomination(Denomination::MilliSatoshi),
+            Some(1_500_000_000)
+        );
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(u64::MAX).to_denomination(Denomination::MilliSatoshi),
+            None
+        );
+    }
+
+    #[test]
+    fn render_in_denomination() {
+        let quantity = BitcoinQuantity::from_satoshi(2_099_999_999_999_999);
+        assert_eq!(
+            quantity.to_string_in(Denomination::Bitcoin),
+            "20999999.99999999"
+        );
+        assert_eq!(
+            quantity.to_string_in(Denomination::CentiBitcoin),
+            "2099999999.999999"
+        );
+        assert_eq!(
+            quantity.to_string_in(Denomination::MilliSatoshi),
+            "2099999999999999000"
+        );
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(120).to_string_in(Denomination::MilliBitcoin),
+            "0.0012"
+        );
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(300).to_string_in(Denomination::Bit),
+            "3"
+        );
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(u64::MAX).to_string_in(Denomination::MilliSatoshi),
+            "18446744073709551615000"
+        );
+    }
+
+    #[test]
+    fn parse_with_unit() {
+        for &(input, sats) in &[
+            ("1.5 BTC", 150_000_000),
+            ("1.5btc", 150_000_000),
+            ("₿1.5", 150_000_000),
+            ("₿ 1.5", 150_000_000),
+            ("1.5 ₿", 150_000_000),
+            ("150 sat", 150),
+            ("150 SATS", 150),
+            ("3 mBTC", 300_000),
+            ("2 cBTC", 2_000_000),
+            ("12.34 bits", 1_234),
+            ("1 µBTC", 100),
+            ("2000 msat", 2),
+            ("2.000 sat", 2),
+            ("1.5 ", 150_000_000),
+            ("1.5 BTC ", 150_000_000),
+            ("₿1.5\t", 150_000_000),
+        ] {
+            assert_that(&BitcoinQuantity::from_str(input))
+                .is_ok_containing(BitcoinQuantity::from_satoshi(sats));
+        }
+    }
+
+    #[test]
+    fn parse_with_unit_rejects_invalid_input() {
+        for &(input, ref error) in &[
+            ("1.5 foo", ParseError::UnknownUnit("foo".to_string())),
+            ("1.5 sat", ParseError::TooManyDecimalPlaces),
+            ("1.123 bits", ParseError::TooManyDecimalPlaces),
+            ("1500 msat", ParseError::TooManyDecimalPlaces),
+            ("BTC", ParseError::InvalidCharacter('B')),
+            ("₿1 BTC", ParseError::InvalidCharacter('₿')),
+            ("-1 BTC", ParseError::Negative),
+            ("21000001 BTC", ParseError::ExceedsMaxMoney),
+            ("1 BTC2", ParseError::InvalidCharacter('2')),
+            ("1 sat 5", ParseError::InvalidCharacter('5')),
+        ] {
+            assert_that(&BitcoinQuantity::from_str(input)).is_err_containing(error);
+        }
+    }
+
+    #[test]
+    fn display_is_exact_near_max_money() {
+        assert_eq!(
+            format!("{}", BitcoinQuantity::from_satoshi(2_099_999_999_999_999)),
+            "20999999.99999999 BTC"
+        );
+        assert_eq!(
+            format!("{}", BitcoinQuantity::from_satoshi(u64::MAX)),
+            "184467440737.09551615 BTC"
+        );
+        assert_eq!(format!("{}", BitcoinQuantity::ONE_SAT), "0.00000001 BTC");
+    }
+
+    #[test]
+    fn display_with_precision() {
+        let quantity = BitcoinQuantity::from_satoshi(123_456_789);
+        assert_eq!(format!("{:.8}", quantity), "1.23456789 BTC");
+        assert_eq!(format!("{:.10}", quantity), "1.2345678900 BTC");
+        assert_that(&format!("{:.10}", quantity).parse()).is_ok_containing(quantity);
+        assert_eq!(format!("{:.4}", quantity), "1.2346 BTC");
+        assert_eq!(format!("{:.0}", quantity), "1 BTC");
+        assert_eq!(
+            format!("{:.7}", BitcoinQuantity::from_satoshi(5)),
+            "0.0000001 BTC"
+        );
+        assert_eq!(format!("{:.8}", BitcoinQuantity::ONE_BTC), "1.00000000 BTC");
+        assert_eq!(
+            format!("{:.2}", BitcoinQuantity::from_satoshi(u64::MAX)),
+            "184467440737.10 BTC"
+        );
+    }
+
+    #[test]
+    fn display_with_width_fill_and_sign() {
+        let quantity = BitcoinQuantity::from_satoshi(150_000_000);
+        assert_eq!(format!("{:>12}", quantity), "     1.5 BTC");
+        assert_eq!(format!("{:*<12}", quantity), "1.5 BTC*****");
+        assert_eq!(format!("{:^11}", quantity), "  1.5 BTC  ");
+        assert_eq!(format!("{:+}", quantity), "+1.5 BTC");
+        assert_eq!(format!("{:>+16.3}", quantity), "      +1.500 BTC");
+    }
+
+    #[test]
+    fn alternate_display_is_in_satoshis() {
+        let quantity = BitcoinQuantity::from_satoshi(150);
+        assert_eq!(format!("{:#}", quantity), "150 sat");
+        assert_eq!(format!("{:>#10}", quantity), "   150 sat");
+        assert_that(&format!("{:#}", quantity).parse()).is_ok_containing(quantity);
+    }
+
+    #[test]
+    fn display_round_trips_through_from_str() {
+        for &sats in &[
+            0,
+            1,
+            12_345,
+            100_000_000,
+            123_456_789_012,
+            2_099_999_999_999_999,
+            2_100_000_000_000_000,
+        ] {
+            let quantity = BitcoinQuantity::from_satoshi(sats);
+            assert_that(&quantity.to_string().parse()).is_ok_containing(quantity);
+        }
+    }
+
+    #[test]
+    fn display_above_max_money_does_not_parse() {
+        let quantity = BitcoinQuantity::from_satoshi(u64::MAX);
+        assert_eq!(quantity.to_string(), "184467440737.09551615 BTC");
+        assert_that(&quantity.to_string().parse::<BitcoinQuantity>())
+            .is_err_containing(ParseError::ExceedsMaxMoney);
+    }
+
+    #[test]
+    fn parse_error_display() {
+        assert_eq!(
+            ParseError::InvalidCharacter('x').to_string(),
+            "invalid character 'x' in amount"
+        );
     }
 }
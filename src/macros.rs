@@ -0,0 +1,261 @@
+/// Implements a binary operator for all combinations of owned and borrowed
+/// operands, forwarding to the implementation for owned operands.
+macro_rules! forward_ref_binop {
+    (impl $imp:ident, $method:ident for $t:ty, $u:ty) => {
+        impl<'a> $imp<$u> for &'a $t {
+            type Output = <$t as $imp<$u>>::Output;
+
+            fn $method(self, rhs: $u) -> Self::Output {
+                $imp::$method(*self, rhs)
+            }
+        }
+
+        impl<'a> $imp<&'a $u> for $t {
+            type Output = <$t as $imp<$u>>::Output;
+
+            fn $method(self, rhs: &'a $u) -> Self::Output {
+                $imp::$method(self, *rhs)
+            }
+        }
+
+        impl<'a, 'b> $imp<&'a $u> for &'b $t {
+            type Output = <$t as $imp<$u>>::Output;
+
+            fn $method(self, rhs: &'a $u) -> Self::Output {
+                $imp::$method(*self, *rhs)
+            }
+        }
+    };
+}
+
+/// Implements a compound assignment operator for owned and borrowed right-hand
+/// sides by forwarding to the corresponding binary operator, so it follows the
+/// same overflow policy.
+macro_rules! assign_op {
+    (impl $imp:ident, $method:ident for $t:ty, $u:ty, $binop:ident, $binop_method:ident) => {
+        impl $imp<$u> for $t {
+            fn $method(&mut self, rhs: $u) {
+                *self = $binop::$binop_method(*self, rhs);
+            }
+        }
+
+        impl<'a> $imp<&'a $u> for $t {
+            fn $method(&mut self, rhs: &'a $u) {
+                *self = $binop::$binop_method(*self, *rhs);
+            }
+        }
+    };
+}
+
+/// Implements the arithmetic shared by the unsigned amount newtypes around a
+/// `u64`: the `checked_*`, `saturating_*` and `overflowing_*` methods, the
+/// operators for owned and borrowed operands, their compound assignment forms,
+/// `Sum` and `CheckedSum`. `$unit` names the amount in panic messages.
+macro_rules! impl_unsigned_amount_ops {
+    ($t:ident, $unit:literal) => {
+        impl $t {
+            /// Checked addition. Returns `None` if the result would overflow.
+            pub fn checked_add(self, rhs: $t) -> Option<$t> {
+                self.0.checked_add(rhs.0).map($t)
+            }
+
+            /// Checked subtraction. Returns `None` if `rhs` is larger than
+            /// `self`.
+            pub fn checked_sub(self, rhs: $t) -> Option<$t> {
+                self.0.checked_sub(rhs.0).map($t)
+            }
+
+            /// Saturating addition. Clamps the result at the largest
+            /// representable quantity instead of overflowing.
+            pub fn saturating_add(self, rhs: $t) -> $t {
+                $t(self.0.saturating_add(rhs.0))
+            }
+
+            /// Saturating subtraction. Clamps the result at zero instead of
+            /// going negative.
+            pub fn saturating_sub(self, rhs: $t) -> $t {
+                $t(self.0.saturating_sub(rhs.0))
+            }
+
+            /// Wrapping addition that also reports whether an overflow
+            /// occurred.
+            pub fn overflowing_add(self, rhs: $t) -> ($t, bool) {
+                let (value, overflow) = self.0.overflowing_add(rhs.0);
+                ($t(value), overflow)
+            }
+
+            /// Wrapping subtraction that also reports whether an underflow
+            /// occurred.
+            pub fn overflowing_sub(self, rhs: $t) -> ($t, bool) {
+                let (value, overflow) = self.0.overflowing_sub(rhs.0);
+                ($t(value), overflow)
+            }
+
+            /// Checked multiplication by a scalar. Returns `None` if the
+            /// result would overflow.
+            pub fn checked_mul(self, rhs: u64) -> Option<$t> {
+                self.0.checked_mul(rhs).map($t)
+            }
+
+            /// Checked division by a scalar, rounding towards zero. Returns
+            /// `None` if `rhs` is zero.
+            pub fn checked_div(self, rhs: u64) -> Option<$t> {
+                self.0.checked_div(rhs).map($t)
+            }
+
+            /// Checked remainder of the division by a scalar. Returns `None`
+            /// if `rhs` is zero.
+            pub fn checked_rem(self, rhs: u64) -> Option<$t> {
+                self.0.checked_rem(rhs).map($t)
+            }
+
+            /// Saturating multiplication by a scalar. Clamps the result at the
+            /// largest representable quantity instead of overflowing.
+            pub fn saturating_mul(self, rhs: u64) -> $t {
+                $t(self.0.saturating_mul(rhs))
+            }
+        }
+
+        /// Operator arithmetic never wraps: `+`, `-` and `*` panic on overflow
+        /// and underflow in every build profile, not only in debug builds.
+        /// Use the `checked_*`, `saturating_*` or `overflowing_*` methods
+        /// where the operands are not known to be in range.
+        impl Add for $t {
+            type Output = $t;
+
+            fn add(self, rhs: $t) -> $t {
+                self.checked_add(rhs)
+                    .expect(concat!("overflow when adding ", $unit, " quantities"))
+            }
+        }
+
+        /// See the `Add` impl for the overflow policy.
+        impl Sub for $t {
+            type Output = $t;
+
+            fn sub(self, rhs: $t) -> $t {
+                self.checked_sub(rhs)
+                    .expect(concat!("underflow when subtracting ", $unit, " quantities"))
+            }
+        }
+
+        /// See the `Add` impl for the overflow policy.
+        impl Mul<u64> for $t {
+            type Output = $t;
+
+            fn mul(self, rhs: u64) -> $t {
+                self.checked_mul(rhs)
+                    .expect(concat!("overflow when multiplying a ", $unit, " quantity"))
+            }
+        }
+
+        impl Mul<$t> for u64 {
+            type Output = $t;
+
+            fn mul(self, rhs: $t) -> $t {
+                rhs * self
+            }
+        }
+
+        /// Rounds towards zero and panics if `rhs` is zero, like integer
+        /// division.
+        impl Div<u64> for $t {
+            type Output = $t;
+
+            fn div(self, rhs: u64) -> $t {
+                self.checked_div(rhs)
+                    .expect(concat!("division of a ", $unit, " quantity by zero"))
+            }
+        }
+
+        /// Panics if `rhs` is zero, like integer division.
+        impl Rem<u64> for $t {
+            type Output = $t;
+
+            fn rem(self, rhs: u64) -> $t {
+                self.checked_rem(rhs)
+                    .expect(concat!("division of a ", $unit, " quantity by zero"))
+            }
+        }
+
+        /// The ratio of `self` to `rhs`, e.g. `0.25` for a quarter. Panics if
+        /// `rhs` is zero rather than returning an infinite or NaN ratio.
+        impl Div for $t {
+            type Output = f64;
+
+            fn div(self, rhs: $t) -> f64 {
+                assert!(rhs.0 != 0, concat!("division of a ", $unit, " quantity by zero"));
+                self.0 as f64 / rhs.0 as f64
+            }
+        }
+
+        /// What is left of `self` after taking out `rhs` as many whole times
+        /// as possible. Panics if `rhs` is zero.
+        impl Rem for $t {
+            type Output = $t;
+
+            fn rem(self, rhs: $t) -> $t {
+                self.checked_rem(rhs.0)
+                    .expect(concat!("division of a ", $unit, " quantity by zero"))
+            }
+        }
+
+        forward_ref_binop!(impl Add, add for $t, $t);
+        forward_ref_binop!(impl Sub, sub for $t, $t);
+        forward_ref_binop!(impl Mul, mul for $t, u64);
+        forward_ref_binop!(impl Mul, mul for u64, $t);
+        forward_ref_binop!(impl Div, div for $t, u64);
+        forward_ref_binop!(impl Rem, rem for $t, u64);
+        forward_ref_binop!(impl Div, div for $t, $t);
+        forward_ref_binop!(impl Rem, rem for $t, $t);
+
+        assign_op!(impl AddAssign, add_assign for $t, $t, Add, add);
+        assign_op!(impl SubAssign, sub_assign for $t, $t, Sub, sub);
+        assign_op!(impl MulAssign, mul_assign for $t, u64, Mul, mul);
+        assign_op!(impl DivAssign, div_assign for $t, u64, Div, div);
+        assign_op!(impl RemAssign, rem_assign for $t, u64, Rem, rem);
+        assign_op!(impl RemAssign, rem_assign for $t, $t, Rem, rem);
+
+        /// Panics if the total overflows, like `+`. Use
+        /// `CheckedSum::checked_sum` if the total is not known to be in range.
+        impl Sum for $t {
+            fn sum<I>(iter: I) -> $t
+            where
+                I: Iterator<Item = $t>,
+            {
+                iter.fold($t::ZERO, Add::add)
+            }
+        }
+
+        impl<'a> Sum<&'a $t> for $t {
+            fn sum<I>(iter: I) -> $t
+            where
+                I: Iterator<Item = &'a $t>,
+            {
+                iter.cloned().sum()
+            }
+        }
+
+        impl<I> ::CheckedSum<$t> for I
+        where
+            I: Iterator<Item = $t>,
+        {
+            type Output = $t;
+
+            fn checked_sum(mut self) -> Option<$t> {
+                self.try_fold($t::ZERO, $t::checked_add)
+            }
+        }
+
+        impl<'a, I> ::CheckedSum<&'a $t> for I
+        where
+            I: Iterator<Item = &'a $t>,
+        {
+            type Output = $t;
+
+            fn checked_sum(self) -> Option<$t> {
+                ::CheckedSum::<$t>::checked_sum(self.cloned())
+            }
+        }
+    };
+}
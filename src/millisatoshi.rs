@@ -0,0 +1,365 @@
+#[cfg(feature = "serde")]
+use serde::{
+    de::{self, Deserialize, Deserializer},
+    ser::{Serialize, Serializer},
+};
+use std::{
+    convert::TryFrom,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign},
+    str::FromStr,
+};
+use {
+    fixed_decimal, parse_decimal, split_unit, trimmed_decimal, BitcoinQuantity, Denomination,
+    OutOfRangeError, ParseError, RoundingMode,
+};
+
+const MSATS_PER_SAT: u64 = 1_000;
+
+/// The number of decimal places of a bitcoin amount in millisatoshis.
+const BITCOIN_PLACES: usize = 11;
+
+/// A bitcoin quantity with millisatoshi precision, as used by Lightning.
+#[derive(PartialEq, Clone, Debug, Copy, PartialOrd, Ord, Eq, Hash)]
+pub struct MilliSatoshiQuantity(u64);
+
+impl MilliSatoshiQuantity {
+    pub const ZERO: MilliSatoshiQuantity = MilliSatoshiQuantity(0);
+    pub const ONE_MSAT: MilliSatoshiQuantity = MilliSatoshiQuantity(1);
+    /// `BitcoinQuantity::MAX_MONEY` in millisatoshis.
+    pub const MAX_MONEY: MilliSatoshiQuantity =
+        MilliSatoshiQuantity(21_000_000 * 100_000_000 * MSATS_PER_SAT);
+
+    pub fn from_msat(msats: u64) -> Self {
+        MilliSatoshiQuantity(msats)
+    }
+    /// Like `from_msat` but rejects amounts above `MAX_MONEY`.
+    pub fn try_from_msat(msats: u64) -> Result<Self, OutOfRangeError> {
+        let quantity = MilliSatoshiQuantity(msats);
+        if quantity <= MilliSatoshiQuantity::MAX_MONEY {
+            Ok(quantity)
+        } else {
+            Err(OutOfRangeError)
+        }
+    }
+    pub fn msat(self) -> u64 {
+        self.0
+    }
+    pub fn bitcoin(self) -> f64 {
+        (self.0 as f64) / 100_000_000_000.0
+    }
+
+    /// Converts to whole satoshis, rounding away the sub-satoshi part using
+    /// `mode`. Never overflows.
+    pub fn to_satoshi(self, mode: RoundingMode) -> BitcoinQuantity {
+        let sats = mode.divide(u128::from(self.0), u128::from(MSATS_PER_SAT));
+        BitcoinQuantity::from_satoshi(sats as u64)
+    }
+    /// Converts to whole satoshis. Returns `None` if the amount has a
+    /// sub-satoshi part.
+    pub fn to_satoshi_exact(self) -> Option<BitcoinQuantity> {
+        if self.0.is_multiple_of(MSATS_PER_SAT) {
+            Some(BitcoinQuantity::from_satoshi(self.0 / MSATS_PER_SAT))
+        } else {
+            None
+        }
+    }
+
+    /// Renders the quantity exactly in `denomination`, without the unit and
+    /// with trailing zeros in the fractional part trimmed.
+    pub fn to_string_in(self, denomination: Denomination) -> String {
+        trimmed_decimal(self.0, places(denomination))
+    }
+    /// Parses a decimal amount without unit, expressed in `denomination`.
+    pub fn from_str_in(string: &str, denomination: Denomination) -> Result<Self, ParseError> {
+        let msats = parse_decimal(string, places(denomination) as i32)?;
+        Ok(MilliSatoshiQuantity::try_from_msat(msats)?)
+    }
+}
+
+/// The number of decimal places of a millisatoshi amount in `denomination`.
+fn places(denomination: Denomination) -> usize {
+    (denomination.precision() + 3) as usize
+}
+
+impl_unsigned_amount_ops!(MilliSatoshiQuantity, "millisatoshi");
+
+/// Exact. Fails if the quantity does not fit into a `u64` of millisatoshis.
+impl TryFrom<BitcoinQuantity> for MilliSatoshiQuantity {
+    type Error = OutOfRangeError;
+
+    fn try_from(quantity: BitcoinQuantity) -> Result<MilliSatoshiQuantity, OutOfRangeError> {
+        quantity
+            .satoshi()
+            .checked_mul(MSATS_PER_SAT)
+            .map(MilliSatoshiQuantity)
+            .ok_or(OutOfRangeError)
+    }
+}
+
+/// Renders the quantity in bitcoin with trailing zeros trimmed, with the same
+/// formatting options as `BitcoinQuantity`, e.g. `"0.00000000001 BTC"`. The
+/// alternate flag (`{:#}`) renders it in millisatoshis, e.g. `"1 msat"`.
+impl fmt::Display for MilliSatoshiQuantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let rendered = if f.alternate() {
+            format!("{} {}", self.0, Denomination::MilliSatoshi)
+        } else {
+            let number = match f.precision() {
+                None => trimmed_decimal(self.0, BITCOIN_PLACES),
+                Some(places) => fixed_decimal(self.0, BITCOIN_PLACES, places),
+            };
+            format!("{} {}", number, Denomination::Bitcoin)
+        };
+
+        f.pad_integral(true, "", &rendered)
+    }
+}
+
+impl FromStr for MilliSatoshiQuantity {
+    type Err = ParseError;
+
+    /// Parses a decimal amount with an optional unit that defaults to
+    /// bitcoin, following the same rules as `BitcoinQuantity` but allowing
+    /// three more decimal places, e.g. `"0.00000000001"` or `"1500 msat"`.
+    /// As there, the output of `Display` is accepted for every amount up to
+    /// `MAX_MONEY`.
+    fn from_str(string: &str) -> Result<MilliSatoshiQuantity, Self::Err> {
+        let (number, denomination) = split_unit(string)?;
+        MilliSatoshiQuantity::from_str_in(number, denomination)
+    }
+}
+
+/// Accepts the number of millisatoshis as a string or an integer, like
+/// `BitcoinQuantity` does for satoshis.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for MilliSatoshiQuantity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'vde> de::Visitor<'vde> for Visitor {
+            type Value = MilliSatoshiQuantity;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                formatter.write_str("A string or integer representing a millisatoshi quantity")
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<MilliSatoshiQuantity, E>
+            where
+                E: de::Error,
+            {
+                MilliSatoshiQuantity::try_from_msat(v).map_err(|e| E::custom(ParseError::from(e)))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<MilliSatoshiQuantity, E>
+            where
+                E: de::Error,
+            {
+                if v < 0 {
+                    return Err(E::custom(ParseError::Negative));
+                }
+                self.visit_u64(v as u64)
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<MilliSatoshiQuantity, E>
+            where
+                E: de::Error,
+            {
+                let msats = super::parse_satoshi(v).map_err(E::custom)?;
+                self.visit_u64(msats)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(Visitor)
+        } else {
+            deserializer.deserialize_u64(Visitor)
+        }
+    }
+}
+
+/// Serializes the number of millisatoshis as a string in human readable
+/// formats and as a plain `u64` in binary formats.
+#[cfg(feature = "serde")]
+impl Serialize for MilliSatoshiQuantity {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.0.to_string().as_str())
+        } else {
+            serializer.serialize_u64(self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate bincode;
+    extern crate serde_json;
+    extern crate spectral;
+
+    use self::spectral::prelude::*;
+    use super::*;
+    use CheckedSum;
+
+    #[test]
+    fn convert_to_satoshi_with_rounding_modes() {
+        let quantity = MilliSatoshiQuantity::from_msat(1_500);
+        assert_eq!(
+            quantity.to_satoshi(RoundingMode::Floor),
+            BitcoinQuantity::from_satoshi(1)
+        );
+        assert_eq!(
+            quantity.to_satoshi(RoundingMode::Ceil),
+            BitcoinQuantity::from_satoshi(2)
+        );
+        assert_eq!(
+            quantity.to_satoshi(RoundingMode::HalfEven),
+            BitcoinQuantity::from_satoshi(2)
+        );
+        assert_eq!(
+            MilliSatoshiQuantity::from_msat(u64::MAX).to_satoshi(RoundingMode::Ceil),
+            BitcoinQuantity::from_satoshi(u64::MAX / 1_000 + 1)
+        );
+        assert_that(&quantity.to_satoshi_exact()).is_none();
+        assert_eq!(
+            MilliSatoshiQuantity::from_msat(2_000).to_satoshi_exact(),
+            Some(BitcoinQuantity::from_satoshi(2))
+        );
+    }
+
+    #[test]
+    fn convert_from_bitcoin_quantity() {
+        assert_that(&MilliSatoshiQuantity::try_from(BitcoinQuantity::MAX_MONEY))
+            .is_ok_containing(MilliSatoshiQuantity::MAX_MONEY);
+        assert_that(&MilliSatoshiQuantity::try_from(
+            BitcoinQuantity::from_satoshi(u64::MAX),
+        ))
+        .is_err_containing(OutOfRangeError);
+    }
+
+    #[test]
+    fn operators_follow_overflow_policy() {
+        let mut quantity = MilliSatoshiQuantity::from_msat(1_500);
+        quantity += MilliSatoshiQuantity::ONE_MSAT;
+        quantity *= 2;
+        assert_eq!(quantity, MilliSatoshiQuantity::from_msat(3_002));
+        assert_eq!(&quantity / 3, MilliSatoshiQuantity::from_msat(1_000));
+        assert_eq!(
+            [quantity, quantity].iter().sum::<MilliSatoshiQuantity>(),
+            MilliSatoshiQuantity::from_msat(6_004)
+        );
+        assert_that(&MilliSatoshiQuantity::ZERO.checked_sub(MilliSatoshiQuantity::ONE_MSAT))
+            .is_none();
+        assert_that(&[quantity, quantity].iter().checked_sum())
+            .is_some()
+            .is_equal_to(MilliSatoshiQuantity::from_msat(6_004));
+        assert_that(
+            &[quantity, MilliSatoshiQuantity::from_msat(u64::MAX)]
+                .iter()
+                .checked_sum(),
+        )
+        .is_none();
+    }
+
+    #[test]
+    fn division_and_remainder() {
+        let quantity = MilliSatoshiQuantity::from_msat(3_002);
+        let sat = MilliSatoshiQuantity::from_msat(1_000);
+        assert_eq!(quantity / sat, 3.002);
+        assert_eq!(quantity % sat, MilliSatoshiQuantity::from_msat(2));
+        assert_eq!(quantity % 3, MilliSatoshiQuantity::from_msat(2));
+        assert_eq!(2 * sat, MilliSatoshiQuantity::from_msat(2_000));
+        assert_that(&quantity.checked_div(0)).is_none();
+        assert_that(&quantity.checked_rem(0)).is_none();
+        assert_eq!(
+            MilliSatoshiQuantity::ZERO.overflowing_sub(MilliSatoshiQuantity::ONE_MSAT),
+            (MilliSatoshiQuantity::from_msat(u64::MAX), true)
+        );
+        assert_eq!(
+            MilliSatoshiQuantity::from_msat(u64::MAX).saturating_mul(2),
+            MilliSatoshiQuantity::from_msat(u64::MAX)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "division of a millisatoshi quantity by zero")]
+    fn div_panics_on_zero() {
+        let _ = MilliSatoshiQuantity::ONE_MSAT / 0;
+    }
+
+    #[test]
+    #[should_panic(expected = "underflow when subtracting millisatoshi quantities")]
+    fn sub_panics_on_underflow() {
+        let _ = MilliSatoshiQuantity::ZERO - MilliSatoshiQuantity::ONE_MSAT;
+    }
+
+    #[test]
+    fn display_millisatoshi_quantity() {
+        let quantity = MilliSatoshiQuantity::from_msat(150_000_000_001);
+        assert_eq!(format!("{}", quantity), "1.50000000001 BTC");
+        assert_eq!(format!("{:.2}", quantity), "1.50 BTC");
+        assert_eq!(format!("{:#}", quantity), "150000000001 msat");
+        assert_eq!(
+            format!("{}", MilliSatoshiQuantity::from_msat(100_000_000_000)),
+            "1 BTC"
+        );
+    }
+
+    #[test]
+    fn parse_millisatoshi_quantity() {
+        assert_that(&MilliSatoshiQuantity::from_str("0.00000000001"))
+            .is_ok_containing(MilliSatoshiQuantity::ONE_MSAT);
+        assert_that(&MilliSatoshiQuantity::from_str("1.5 sat"))
+            .is_ok_containing(MilliSatoshiQuantity::from_msat(1_500));
+        assert_that(&MilliSatoshiQuantity::from_str("1500 msat"))
+            .is_ok_containing(MilliSatoshiQuantity::from_msat(1_500));
+        assert_that(&MilliSatoshiQuantity::from_str("0.000000000001"))
+            .is_err_containing(ParseError::TooManyDecimalPlaces);
+        assert_that(&MilliSatoshiQuantity::from_str("21000000.00000000001"))
+            .is_err_containing(ParseError::ExceedsMaxMoney);
+    }
+
+    #[test]
+    fn display_round_trips_through_from_str() {
+        for &msats in &[0, 1, 999, 1_000, 150_000_000_001, 2_100_000_000_000_000_000] {
+            let quantity = MilliSatoshiQuantity::from_msat(msats);
+            assert_that(&MilliSatoshiQuantity::from_str(&quantity.to_string()))
+                .is_ok_containing(quantity);
+            assert_that(&MilliSatoshiQuantity::from_str(&format!("{:#}", quantity)))
+                .is_ok_containing(quantity);
+        }
+    }
+
+    #[test]
+    fn display_above_max_money_does_not_parse() {
+        let quantity = MilliSatoshiQuantity::from_msat(u64::MAX);
+        assert_that(&MilliSatoshiQuantity::from_str(&quantity.to_string()))
+            .is_err_containing(ParseError::ExceedsMaxMoney);
+        assert_that(&MilliSatoshiQuantity::from_str(&format!("{:#}", quantity)))
+            .is_err_containing(ParseError::ExceedsMaxMoney);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let quantity = MilliSatoshiQuantity::from_msat(1_500);
+        assert_eq!(serde_json::to_string(&quantity).unwrap(), "\"1500\"");
+        assert_eq!(
+            serde_json::from_str::<MilliSatoshiQuantity>("1500").unwrap(),
+            quantity
+        );
+        assert_eq!(
+            bincode::deserialize::<MilliSatoshiQuantity>(&bincode::serialize(&quantity).unwrap())
+                .unwrap(),
+            quantity
+        );
+        assert!(serde_json::from_str::<MilliSatoshiQuantity>("\"-1\"").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use BitcoinQuantity;
+
+/// How to round a result that falls between two whole satoshis.
+#[derive(PartialEq, Clone, Debug, Copy, Eq, Hash)]
+pub enum RoundingMode {
+    /// Round down to the next whole satoshi.
+    Floor,
+    /// Round up to the next whole satoshi.
+    Ceil,
+    /// Round to the nearest satoshi, and to the even one if both are equally
+    /// near ("banker's rounding").
+    HalfEven,
+    /// Round to the nearest satoshi, and up if both are equally near.
+    HalfUp,
+}
+
+impl RoundingMode {
+    pub(crate) fn divide(self, numerator: u128, denominator: u128) -> u128 {
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        let round_up = match self {
+            RoundingMode::Floor => false,
+            RoundingMode::Ceil => remainder > 0,
+            RoundingMode::HalfUp => remainder * 2 >= denominator,
+            RoundingMode::HalfEven => {
+                remainder * 2 > denominator || (remainder * 2 == denominator && quotient % 2 == 1)
+            }
+        };
+
+        if round_up {
+            quotient + 1
+        } else {
+            quotient
+        }
+    }
+}
+
+impl BitcoinQuantity {
+    /// Computes `self * numerator / denominator` exactly with integer math and
+    /// rounds the result to a whole satoshi using `mode`. Returns `None` if
+    /// `denominator` is zero or the result does not fit into a `u64`.
+    pub fn mul_div(
+        self,
+        numerator: u64,
+        denominator: u64,
+        mode: RoundingMode,
+    ) -> Option<BitcoinQuantity> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let scaled = u128::from(self.satoshi()) * u128::from(numerator);
+        let sats = mode.divide(scaled, u128::from(denominator));
+        if sats > u128::from(u64::MAX) {
+            return None;
+        }
+
+        Some(BitcoinQuantity::from_satoshi(sats as u64))
+    }
+
+    /// `percent` percent of the quantity, rounded using `mode`. Returns `None`
+    /// if the result does not fit into a `u64`.
+    pub fn percent(self, percent: u64, mode: RoundingMode) -> Option<BitcoinQuantity> {
+        self.mul_div(percent, 100, mode)
+    }
+
+    /// `basis_points` hundredths of a percent of the quantity, e.g. 25 for a
+    /// 0.25% fee, rounded using `mode`. Returns `None` if the result does not
+    /// fit into a `u64`.
+    pub fn basis_points(self, basis_points: u64, mode: RoundingMode) -> Option<BitcoinQuantity> {
+        self.mul_div(basis_points, 10_000, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee(sats: u64, basis_points: u64, mode: RoundingMode) -> u64 {
+        BitcoinQuantity::from_satoshi(sats)
+            .basis_points(basis_points, mode)
+            .unwrap()
+            .satoshi()
+    }
+
+    #[test]
+    fn basis_points_with_rounding_modes() {
+        // 0.25% of 1002 sats is 2.505 sats
+        assert_eq!(fee(1_002, 25, RoundingMode::Floor), 2);
+        assert_eq!(fee(1_002, 25, RoundingMode::Ceil), 3);
+        assert_eq!(fee(1_002, 25, RoundingMode::HalfUp), 3);
+        assert_eq!(fee(1_002, 25, RoundingMode::HalfEven), 3);
+
+        // 0.25% of 1000 sats is 2.5 sats
+        assert_eq!(fee(1_000, 25, RoundingMode::Floor), 2);
+        assert_eq!(fee(1_000, 25, RoundingMode::Ceil), 3);
+        assert_eq!(fee(1_000, 25, RoundingMode::HalfUp), 3);
+        assert_eq!(fee(1_000, 25, RoundingMode::HalfEven), 2);
+
+        // 0.25% of 1400 sats is 3.5 sats
+        assert_eq!(fee(1_400, 25, RoundingMode::HalfEven), 4);
+
+        // exact results are never rounded
+        assert_eq!(fee(100_000_000, 25, RoundingMode::Ceil), 250_000);
+        assert_eq!(fee(100_000_000, 25, RoundingMode::Floor), 250_000);
+    }
+
+    #[test]
+    fn percent_of_quantity() {
+        let quantity = BitcoinQuantity::from_satoshi(1_001);
+        assert_eq!(
+            quantity.percent(50, RoundingMode::Floor),
+            Some(BitcoinQuantity::from_satoshi(500))
+        );
+        assert_eq!(
+            quantity.percent(50, RoundingMode::HalfEven),
+            Some(BitcoinQuantity::from_satoshi(500))
+        );
+        assert_eq!(
+            quantity.percent(50, RoundingMode::HalfUp),
+            Some(BitcoinQuantity::from_satoshi(501))
+        );
+        assert_eq!(
+            BitcoinQuantity::from_satoshi(u64::MAX).percent(200, RoundingMode::Floor),
+            None
+        );
+    }
+
+    #[test]
+    fn mul_div_without_intermediate_overflow() {
+        let quantity = BitcoinQuantity::from_satoshi(u64::MAX);
+        assert_eq!(
+            quantity.mul_div(u64::MAX, u64::MAX, RoundingMode::Floor),
+            Some(quantity)
+        );
+        assert_eq!(quantity.mul_div(1, 0, RoundingMode::Floor), None);
+    }
+}
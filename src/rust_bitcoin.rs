@@ -0,0 +1,134 @@
+//! Conversions between the quantities of this crate and the amount types of
+//! the `bitcoin` crate.
+
+use bitcoin::{Amount, SignedAmount, Transaction, TxOut};
+use std::convert::TryFrom;
+use {BitcoinQuantity, CheckedSum, OutOfRangeError, SignedBitcoinQuantity};
+
+impl From<BitcoinQuantity> for Amount {
+    fn from(quantity: BitcoinQuantity) -> Amount {
+        Amount::from_sat(quantity.satoshi())
+    }
+}
+
+impl From<Amount> for BitcoinQuantity {
+    fn from(amount: Amount) -> BitcoinQuantity {
+        BitcoinQuantity::from_satoshi(amount.to_sat())
+    }
+}
+
+impl From<SignedBitcoinQuantity> for SignedAmount {
+    fn from(quantity: SignedBitcoinQuantity) -> SignedAmount {
+        SignedAmount::from_sat(quantity.satoshi())
+    }
+}
+
+impl From<SignedAmount> for SignedBitcoinQuantity {
+    fn from(amount: SignedAmount) -> SignedBitcoinQuantity {
+        SignedBitcoinQuantity::from_satoshi(amount.to_sat())
+    }
+}
+
+impl TryFrom<BitcoinQuantity> for SignedAmount {
+    type Error = OutOfRangeError;
+
+    fn try_from(quantity: BitcoinQuantity) -> Result<SignedAmount, OutOfRangeError> {
+        SignedBitcoinQuantity::try_from(quantity).map(SignedAmount::from)
+    }
+}
+
+impl TryFrom<SignedAmount> for BitcoinQuantity {
+    type Error = OutOfRangeError;
+
+    fn try_from(amount: SignedAmount) -> Result<BitcoinQuantity, OutOfRangeError> {
+        BitcoinQuantity::try_from(SignedBitcoinQuantity::from(amount))
+    }
+}
+
+impl<'a> From<&'a TxOut> for BitcoinQuantity {
+    fn from(tx_out: &'a TxOut) -> BitcoinQuantity {
+        BitcoinQuantity::from(tx_out.value)
+    }
+}
+
+impl BitcoinQuantity {
+    /// The value of a transaction output.
+    pub fn from_tx_out(tx_out: &TxOut) -> BitcoinQuantity {
+        BitcoinQuantity::from(tx_out)
+    }
+
+    /// The sum of all output values of a transaction. Returns `None` if the
+    /// sum overflows.
+    pub fn total_output_value(transaction: &Transaction) -> Option<BitcoinQuantity> {
+        transaction
+            .output
+            .iter()
+            .map(BitcoinQuantity::from_tx_out)
+            .checked_sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{absolute::LockTime, transaction::Version, ScriptBuf};
+
+    fn tx_out(sats: u64) -> TxOut {
+        TxOut {
+            value: Amount::from_sat(sats),
+            script_pubkey: ScriptBuf::new(),
+        }
+    }
+
+    #[test]
+    fn convert_to_and_from_amount() {
+        let quantity = BitcoinQuantity::from_satoshi(12_345);
+        assert_eq!(Amount::from(quantity), Amount::from_sat(12_345));
+        assert_eq!(BitcoinQuantity::from(Amount::from_sat(12_345)), quantity);
+
+        let quantity = SignedBitcoinQuantity::from_satoshi(-12_345);
+        assert_eq!(
+            SignedAmount::from(quantity),
+            SignedAmount::from_sat(-12_345)
+        );
+        assert_eq!(
+            SignedBitcoinQuantity::from(SignedAmount::from_sat(-12_345)),
+            quantity
+        );
+    }
+
+    #[test]
+    fn convert_between_unsigned_and_signed_amount() {
+        assert_eq!(
+            SignedAmount::try_from(BitcoinQuantity::ONE_SAT),
+            Ok(SignedAmount::from_sat(1))
+        );
+        assert_eq!(
+            SignedAmount::try_from(BitcoinQuantity::from_satoshi(u64::MAX)),
+            Err(OutOfRangeError)
+        );
+        assert_eq!(
+            BitcoinQuantity::try_from(SignedAmount::from_sat(-1)),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn read_output_values() {
+        let transaction = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![tx_out(1_000), tx_out(2_500)],
+        };
+
+        assert_eq!(
+            BitcoinQuantity::from_tx_out(&transaction.output[1]),
+            BitcoinQuantity::from_satoshi(2_500)
+        );
+        assert_eq!(
+            BitcoinQuantity::total_output_value(&transaction),
+            Some(BitcoinQuantity::from_satoshi(3_500))
+        );
+    }
+}
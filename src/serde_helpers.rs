@@ -0,0 +1,385 @@
+//! Alternative serde representations of `BitcoinQuantity`, to be picked per
+//! field with `#[serde(with = "...")]`:
+//!
+//! - `as_sat`: the number of satoshis as an integer, e.g. `100000000`
+//! - `as_sat_str`: the number of satoshis as a string, e.g. `"100000000"`
+//! - `as_btc`: the amount in bitcoin as a float, e.g. `1.0`
+//! - `as_btc_str`: the amount in bitcoin as a decimal string, e.g. `"1"`
+//! - `with_unit`: the default representation, but deserializing also accepts
+//!   strings with a unit, e.g. `"1.5 BTC"` or `"150 sat"`
+//! - `as_rpc_btc`: a JSON number in bitcoin as used by Bitcoin Core's RPC,
+//!   e.g. `0.00012345` (requires the `rpc` feature)
+//!
+//! Every module has an `opt` submodule for `Option<BitcoinQuantity>` fields.
+//! Combine those with `#[serde(default)]` to accept a missing field. All of
+//! them reject amounts above `BitcoinQuantity::MAX_MONEY` when deserializing.
+
+macro_rules! opt_module {
+    () => {
+        /// The same representation for an `Option<BitcoinQuantity>`.
+        pub mod opt {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+            use BitcoinQuantity;
+
+            struct Wrapper(BitcoinQuantity);
+
+            impl Serialize for Wrapper {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    super::serialize(&self.0, serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for Wrapper {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    super::deserialize(deserializer).map(Wrapper)
+                }
+            }
+
+            pub fn serialize<S>(
+                quantity: &Option<BitcoinQuantity>,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                match *quantity {
+                    Some(quantity) => serializer.serialize_some(&Wrapper(quantity)),
+                    None => serializer.serialize_none(),
+                }
+            }
+
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<BitcoinQuantity>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Option::<Wrapper>::deserialize(deserializer)
+                    .map(|quantity| quantity.map(|wrapper| wrapper.0))
+            }
+        }
+    };
+}
+
+/// Serializes a `BitcoinQuantity` as an integer number of satoshis.
+pub mod as_sat {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use {BitcoinQuantity, ParseError};
+
+    pub fn serialize<S>(quantity: &BitcoinQuantity, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(quantity.satoshi())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BitcoinQuantity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let sats = u64::deserialize(deserializer)?;
+        BitcoinQuantity::try_from_satoshi(sats).map_err(|e| D::Error::custom(ParseError::from(e)))
+    }
+
+    opt_module!();
+}
+
+/// Serializes a `BitcoinQuantity` as a string containing the number of
+/// satoshis.
+pub mod as_sat_str {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use {BitcoinQuantity, ParseError};
+
+    pub fn serialize<S>(quantity: &BitcoinQuantity, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(quantity.satoshi().to_string().as_str())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BitcoinQuantity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        let sats = ::parse_satoshi(&string).map_err(D::Error::custom)?;
+        BitcoinQuantity::try_from_satoshi(sats).map_err(|e| D::Error::custom(ParseError::from(e)))
+    }
+
+    opt_module!();
+}
+
+/// Serializes a `BitcoinQuantity` as a floating point number of bitcoin.
+/// Deserializing rejects values that are not a whole number of satoshis.
+pub mod as_btc {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use BitcoinQuantity;
+
+    pub fn serialize<S>(quantity: &BitcoinQuantity, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_f64(quantity.bitcoin())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BitcoinQuantity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let btc = f64::deserialize(deserializer)?;
+        BitcoinQuantity::try_from_bitcoin(btc).map_err(D::Error::custom)
+    }
+
+    opt_module!();
+}
+
+/// Serializes a `BitcoinQuantity` as an exact decimal string of bitcoin
+/// without unit, e.g. `"0.00012345"`.
+pub mod as_btc_str {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use {BitcoinQuantity, Denomination};
+
+    pub fn serialize<S>(quantity: &BitcoinQuantity, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(quantity.to_string_in(Denomination::Bitcoin).as_str())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BitcoinQuantity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        BitcoinQuantity::from_str_in(&string, Denomination::Bitcoin).map_err(D::Error::custom)
+    }
+
+    opt_module!();
+}
+
+/// Serializes a `BitcoinQuantity` like its `Serialize` impl. Deserializing
+/// additionally accepts strings with a unit such as `"1.5 BTC"` or
+/// `"150 sat"`; strings without unit are still read as satoshis.
+pub mod with_unit {
+    use serde::{Deserializer, Serialize, Serializer};
+    use {BitcoinQuantity, SatoshiVisitor};
+
+    pub fn serialize<S>(quantity: &BitcoinQuantity, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        quantity.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BitcoinQuantity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(SatoshiVisitor { accept_unit: true })
+        } else {
+            deserializer.deserialize_u64(SatoshiVisitor { accept_unit: true })
+        }
+    }
+
+    opt_module!();
+}
+
+/// Serializes a `BitcoinQuantity` as a JSON number of bitcoin with exactly
+/// 8 decimal places, like Bitcoin Core's `ValueFromAmount`. Deserializing
+/// follows Core's `AmountFromValue`: numbers and strings are read exactly
+/// without going through `f64` and more than 8 decimal places are rejected.
+///
+/// The exact number text is read and written through serde_json's `RawValue`,
+/// so this only works with serde_json itself and not through buffering
+/// adapters such as `#[serde(flatten)]` or untagged enums.
+#[cfg(feature = "rpc")]
+pub mod as_rpc_btc {
+    use bitcoin_core::{parse_fixed_point, value_from_amount};
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::value::RawValue;
+    use BitcoinQuantity;
+
+    pub fn serialize<S>(quantity: &BitcoinQuantity, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        RawValue::from_string(value_from_amount(*quantity))
+            .expect("a fixed point amount is a valid JSON number")
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BitcoinQuantity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        let value = match raw.get().chars().next() {
+            Some('"') => serde_json::from_str::<String>(raw.get()).map_err(D::Error::custom)?,
+            Some(c) if c == '-' || c.is_ascii_digit() => raw.get().to_owned(),
+            _ => return Err(D::Error::custom("amount is not a number or string")),
+        };
+
+        parse_fixed_point(&value).map_err(D::Error::custom)
+    }
+
+    opt_module!();
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_derive;
+    extern crate serde_json;
+
+    use self::serde_derive::{Deserialize, Serialize};
+    use BitcoinQuantity;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Payment {
+        #[serde(with = "::serde_helpers::as_sat")]
+        sat: BitcoinQuantity,
+        #[serde(with = "::serde_helpers::as_sat_str")]
+        sat_str: BitcoinQuantity,
+        #[serde(with = "::serde_helpers::as_btc")]
+        btc: BitcoinQuantity,
+        #[serde(with = "::serde_helpers::as_btc_str")]
+        btc_str: BitcoinQuantity,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct OptionalPayment {
+        #[serde(default, with = "::serde_helpers::as_sat::opt")]
+        sat: Option<BitcoinQuantity>,
+        #[serde(default, with = "::serde_helpers::as_btc_str::opt")]
+        btc_str: Option<BitcoinQuantity>,
+    }
+
+    #[test]
+    fn serialize_with_helpers() {
+        let quantity = BitcoinQuantity::from_satoshi(12_345);
+        let payment = Payment {
+            sat: quantity,
+            sat_str: quantity,
+            btc: quantity,
+            btc_str: quantity,
+        };
+        let json = r#"{"sat":12345,"sat_str":"12345","btc":0.00012345,"btc_str":"0.00012345"}"#;
+
+        assert_eq!(serde_json::to_string(&payment).unwrap(), json);
+        assert_eq!(serde_json::from_str::<Payment>(json).unwrap(), payment);
+    }
+
+    #[test]
+    fn serialize_optional_with_helpers() {
+        let payment = OptionalPayment {
+            sat: Some(BitcoinQuantity::ONE_SAT),
+            btc_str: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&payment).unwrap(),
+            r#"{"sat":1,"btc_str":null}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<OptionalPayment>(r#"{"sat":1}"#).unwrap(),
+            payment
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        #[serde(with = "::serde_helpers::with_unit")]
+        max_fee: BitcoinQuantity,
+    }
+
+    #[test]
+    fn deserialize_with_unit() {
+        for &(json, sats) in &[
+            (r#"{"max_fee":"1.5 BTC"}"#, 150_000_000),
+            (r#"{"max_fee":"150 sat"}"#, 150),
+            (r#"{"max_fee":"150"}"#, 150),
+            (r#"{"max_fee":150}"#, 150),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<Config>(json).unwrap(),
+                Config {
+                    max_fee: BitcoinQuantity::from_satoshi(sats),
+                }
+            );
+        }
+
+        let error = serde_json::from_str::<Config>(r#"{"max_fee":"1.5 foo"}"#).unwrap_err();
+        assert!(error.to_string().contains("unknown unit 'foo'"));
+    }
+
+    #[cfg(feature = "rpc")]
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct RpcPayment {
+        #[serde(with = "::serde_helpers::as_rpc_btc")]
+        amount: BitcoinQuantity,
+        #[serde(default, with = "::serde_helpers::as_rpc_btc::opt")]
+        fee: Option<BitcoinQuantity>,
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn serialize_as_rpc_btc() {
+        let payment = RpcPayment {
+            amount: BitcoinQuantity::from_satoshi(2_099_999_999_999_999),
+            fee: Some(BitcoinQuantity::from_satoshi(12_345)),
+        };
+        let json = r#"{"amount":20999999.99999999,"fee":0.00012345}"#;
+
+        assert_eq!(serde_json::to_string(&payment).unwrap(), json);
+        assert_eq!(serde_json::from_str::<RpcPayment>(json).unwrap(), payment);
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn deserialize_as_rpc_btc() {
+        for &(json, sats) in &[
+            (r#"{"amount":1}"#, 100_000_000),
+            (r#"{"amount":0.1}"#, 10_000_000),
+            (r#"{"amount":1e-8}"#, 1),
+            (r#"{"amount":"0.00012345"}"#, 12_345),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<RpcPayment>(json).unwrap().amount,
+                BitcoinQuantity::from_satoshi(sats)
+            );
+        }
+
+        for json in &[
+            r#"{"amount":0.000000001}"#,
+            r#"{"amount":-1}"#,
+            r#"{"amount":21000000.00000001}"#,
+            r#"{"amount":true}"#,
+            r#"{"amount":"01"}"#,
+        ] {
+            assert!(serde_json::from_str::<RpcPayment>(json).is_err());
+        }
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn rpc_feature_leaves_plain_json_numbers_alone() {
+        let error = serde_json::from_str::<BitcoinQuantity>("1e2").unwrap_err();
+        assert!(error.to_string().contains("floating point"));
+    }
+
+    #[test]
+    fn deserialize_with_helpers_rejects_invalid_amounts() {
+        for json in &[
+            r#"{"sat":2100000000000001,"sat_str":"1","btc":1,"btc_str":"1"}"#,
+            r#"{"sat":1,"sat_str":"-1","btc":1,"btc_str":"1"}"#,
+            r#"{"sat":1,"sat_str":"1","btc":0.000000001,"btc_str":"1"}"#,
+            r#"{"sat":1,"sat_str":"1","btc":1,"btc_str":"0.000000001"}"#,
+        ] {
+            assert!(serde_json::from_str::<Payment>(json).is_err());
+        }
+    }
+}
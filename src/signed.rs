@@ -0,0 +1,367 @@
+#[cfg(feature = "serde")]
+use serde::{
+    de::{self, Deserialize, Deserializer},
+    ser::{Serialize, Serializer},
+};
+use std::{
+    convert::TryFrom,
+    fmt,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+use {BitcoinQuantity, OutOfRangeError, ParseError};
+
+/// A bitcoin quantity that can be negative, e.g. a balance change, a net flow
+/// or a fee refund.
+#[derive(PartialEq, Clone, Debug, Copy, PartialOrd, Ord, Eq, Hash)]
+pub struct SignedBitcoinQuantity(i64);
+
+impl SignedBitcoinQuantity {
+    pub const ZERO: SignedBitcoinQuantity = SignedBitcoinQuantity(0);
+
+    pub fn from_satoshi(sats: i64) -> Self {
+        SignedBitcoinQuantity(sats)
+    }
+    pub fn satoshi(self) -> i64 {
+        self.0
+    }
+    pub fn bitcoin(self) -> f64 {
+        (self.0 as f64) / 100_000_000.0
+    }
+
+    /// The absolute value. Panics for the most negative representable
+    /// quantity, following the operator overflow policy.
+    pub fn abs(self) -> SignedBitcoinQuantity {
+        self.checked_abs()
+            .expect("overflow when taking the absolute value of a bitcoin quantity")
+    }
+    pub fn checked_abs(self) -> Option<SignedBitcoinQuantity> {
+        self.0.checked_abs().map(SignedBitcoinQuantity)
+    }
+    /// The absolute value as an unsigned quantity. Never overflows.
+    pub fn unsigned_abs(self) -> BitcoinQuantity {
+        BitcoinQuantity::from_satoshi(self.0.unsigned_abs())
+    }
+
+    /// `-1` if the quantity is negative, `0` if it is zero and `1` if it is
+    /// positive.
+    pub fn signum(self) -> i64 {
+        self.0.signum()
+    }
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Checked addition. Returns `None` if the result would overflow.
+    pub fn checked_add(self, rhs: SignedBitcoinQuantity) -> Option<SignedBitcoinQuantity> {
+        self.0.checked_add(rhs.0).map(SignedBitcoinQuantity)
+    }
+
+    /// Checked subtraction. Returns `None` if the result would overflow.
+    pub fn checked_sub(self, rhs: SignedBitcoinQuantity) -> Option<SignedBitcoinQuantity> {
+        self.0.checked_sub(rhs.0).map(SignedBitcoinQuantity)
+    }
+
+    /// Checked negation. Returns `None` for the most negative representable
+    /// quantity.
+    pub fn checked_neg(self) -> Option<SignedBitcoinQuantity> {
+        self.0.checked_neg().map(SignedBitcoinQuantity)
+    }
+}
+
+/// Follows the same overflow policy as the operators on `BitcoinQuantity`:
+/// `+`, `-` and unary `-` panic on overflow in every build profile.
+impl Add for SignedBitcoinQuantity {
+    type Output = SignedBitcoinQuantity;
+
+    fn add(self, rhs: SignedBitcoinQuantity) -> SignedBitcoinQuantity {
+        self.checked_add(rhs)
+            .expect("overflow when adding bitcoin quantities")
+    }
+}
+
+impl Sub for SignedBitcoinQuantity {
+    type Output = SignedBitcoinQuantity;
+
+    fn sub(self, rhs: SignedBitcoinQuantity) -> SignedBitcoinQuantity {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting bitcoin quantities")
+    }
+}
+
+impl Neg for SignedBitcoinQuantity {
+    type Output = SignedBitcoinQuantity;
+
+    fn neg(self) -> SignedBitcoinQuantity {
+        self.checked_neg()
+            .expect("overflow when negating a bitcoin quantity")
+    }
+}
+
+impl Neg for &SignedBitcoinQuantity {
+    type Output = SignedBitcoinQuantity;
+
+    fn neg(self) -> SignedBitcoinQuantity {
+        -*self
+    }
+}
+
+forward_ref_binop!(impl Add, add for SignedBitcoinQuantity, SignedBitcoinQuantity);
+forward_ref_binop!(impl Sub, sub for SignedBitcoinQuantity, SignedBitcoinQuantity);
+
+assign_op!(impl AddAssign, add_assign for SignedBitcoinQuantity, SignedBitcoinQuantity, Add, add);
+assign_op!(impl SubAssign, sub_assign for SignedBitcoinQuantity, SignedBitcoinQuantity, Sub, sub);
+
+impl TryFrom<BitcoinQuantity> for SignedBitcoinQuantity {
+    type Error = OutOfRangeError;
+
+    fn try_from(quantity: BitcoinQuantity) -> Result<SignedBitcoinQuantity, OutOfRangeError> {
+        i64::try_from(quantity.satoshi())
+            .map(SignedBitcoinQuantity)
+            .map_err(|_| OutOfRangeError)
+    }
+}
+
+impl TryFrom<SignedBitcoinQuantity> for BitcoinQuantity {
+    type Error = OutOfRangeError;
+
+    fn try_from(quantity: SignedBitcoinQuantity) -> Result<BitcoinQuantity, OutOfRangeError> {
+        u64::try_from(quantity.0)
+            .map(BitcoinQuantity::from_satoshi)
+            .map_err(|_| OutOfRangeError)
+    }
+}
+
+/// Supports the same formatting options as the `Display` impl of
+/// `BitcoinQuantity`, with the minus sign placed before the padded number.
+impl fmt::Display for SignedBitcoinQuantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.pad_integral(!self.is_negative(), "", &self.unsigned_abs().render(f))
+    }
+}
+
+impl FromStr for SignedBitcoinQuantity {
+    type Err = ParseError;
+
+    /// Parses a decimal bitcoin amount with an optional leading minus sign,
+    /// following the same rules as `BitcoinQuantity`. The magnitude may not
+    /// exceed `MAX_MONEY`.
+    fn from_str(string: &str) -> Result<SignedBitcoinQuantity, Self::Err> {
+        let (negative, magnitude) = split_sign(string)?;
+        let magnitude = BitcoinQuantity::from_str(magnitude)?;
+
+        Ok(from_magnitude(negative, magnitude))
+    }
+}
+
+fn split_sign(string: &str) -> Result<(bool, &str), ParseError> {
+    if !string.starts_with('-') {
+        return Ok((false, string));
+    }
+
+    let magnitude = &string[1..];
+    if magnitude.starts_with('-') {
+        return Err(ParseError::InvalidCharacter('-'));
+    }
+
+    Ok((true, magnitude))
+}
+
+fn from_magnitude(negative: bool, magnitude: BitcoinQuantity) -> SignedBitcoinQuantity {
+    // Both callers have already checked the magnitude against `MAX_MONEY`,
+    // which is far below `i64::MAX`.
+    let sats = magnitude.satoshi() as i64;
+    if negative {
+        SignedBitcoinQuantity(-sats)
+    } else {
+        SignedBitcoinQuantity(sats)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SignedBitcoinQuantity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'vde> de::Visitor<'vde> for Visitor {
+            type Value = SignedBitcoinQuantity;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                formatter.write_str("A string or integer representing a signed satoshi quantity")
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<SignedBitcoinQuantity, E>
+            where
+                E: de::Error,
+            {
+                let magnitude = BitcoinQuantity::try_from_satoshi(v.unsigned_abs())
+                    .map_err(|e| E::custom(ParseError::from(e)))?;
+
+                Ok(from_magnitude(v < 0, magnitude))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<SignedBitcoinQuantity, E>
+            where
+                E: de::Error,
+            {
+                let magnitude = BitcoinQuantity::try_from_satoshi(v)
+                    .map_err(|e| E::custom(ParseError::from(e)))?;
+
+                Ok(from_magnitude(false, magnitude))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<SignedBitcoinQuantity, E>
+            where
+                E: de::Error,
+            {
+                let (negative, magnitude) = split_sign(v).map_err(E::custom)?;
+                let sats = super::parse_satoshi(magnitude).map_err(E::custom)?;
+                let magnitude = BitcoinQuantity::try_from_satoshi(sats)
+                    .map_err(|e| E::custom(ParseError::from(e)))?;
+
+                Ok(from_magnitude(negative, magnitude))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(Visitor)
+        } else {
+            deserializer.deserialize_i64(Visitor)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SignedBitcoinQuantity {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.0.to_string().as_str())
+        } else {
+            serializer.serialize_i64(self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate bincode;
+    extern crate serde_json;
+    extern crate spectral;
+
+    use self::spectral::prelude::*;
+    use super::*;
+
+    #[test]
+    fn display_negative_quantity() {
+        assert_eq!(
+            format!("{}", SignedBitcoinQuantity::from_satoshi(-50_000_000)),
+            "-0.5 BTC"
+        );
+        assert_eq!(
+            format!("{}", SignedBitcoinQuantity::from_satoshi(100_000_000)),
+            "1 BTC"
+        );
+    }
+
+    #[test]
+    fn display_with_formatting_options() {
+        let quantity = SignedBitcoinQuantity::from_satoshi(-150_000_000);
+        assert_eq!(format!("{:>12.2}", quantity), "   -1.50 BTC");
+        assert_eq!(format!("{:#}", quantity), "-150000000 sat");
+        assert_eq!(format!("{:+}", -quantity), "+1.5 BTC");
+    }
+
+    #[test]
+    fn parse_signed_quantity() {
+        assert_that(&SignedBitcoinQuantity::from_str("-0.5"))
+            .is_ok_containing(SignedBitcoinQuantity::from_satoshi(-50_000_000));
+        assert_that(&SignedBitcoinQuantity::from_str("0.00000001"))
+            .is_ok_containing(SignedBitcoinQuantity::from_satoshi(1));
+        assert_that(&SignedBitcoinQuantity::from_str("--1"))
+            .is_err_containing(ParseError::InvalidCharacter('-'));
+        assert_that(&SignedBitcoinQuantity::from_str("-21000000.00000001"))
+            .is_err_containing(ParseError::ExceedsMaxMoney);
+    }
+
+    #[test]
+    fn neg_abs_and_signum() {
+        let quantity = SignedBitcoinQuantity::from_satoshi(-42);
+        assert_eq!(-quantity, SignedBitcoinQuantity::from_satoshi(42));
+        assert_eq!(quantity.abs(), SignedBitcoinQuantity::from_satoshi(42));
+        assert_eq!(quantity.signum(), -1);
+        assert_eq!(SignedBitcoinQuantity::ZERO.signum(), 0);
+        assert_that(&SignedBitcoinQuantity::from_satoshi(i64::MIN).checked_abs()).is_none();
+    }
+
+    #[test]
+    fn operators_with_references_and_assignment() {
+        let mut net_flow = SignedBitcoinQuantity::ZERO;
+        let refund = SignedBitcoinQuantity::from_satoshi(50);
+        let fee = SignedBitcoinQuantity::from_satoshi(200);
+
+        net_flow -= &fee;
+        net_flow += refund;
+        assert_eq!(net_flow, SignedBitcoinQuantity::from_satoshi(-150));
+        let (refund_ref, fee_ref) = (&refund, &fee);
+        assert_eq!(refund_ref - fee_ref, net_flow);
+        assert_eq!(-&net_flow, SignedBitcoinQuantity::from_satoshi(150));
+    }
+
+    #[test]
+    fn convert_between_signed_and_unsigned() {
+        assert_that(&BitcoinQuantity::try_from(
+            SignedBitcoinQuantity::from_satoshi(-1),
+        ))
+        .is_err_containing(OutOfRangeError);
+        assert_that(&BitcoinQuantity::try_from(
+            SignedBitcoinQuantity::from_satoshi(1),
+        ))
+        .is_ok_containing(BitcoinQuantity::ONE_SAT);
+        assert_that(&SignedBitcoinQuantity::try_from(
+            BitcoinQuantity::from_satoshi(u64::MAX),
+        ))
+        .is_err_containing(OutOfRangeError);
+    }
+
+    #[test]
+    fn difference_of_unsigned_quantities() {
+        let fee = BitcoinQuantity::from_satoshi(300);
+        let refund = BitcoinQuantity::from_satoshi(100);
+        assert_eq!(
+            refund.signed_sub(fee),
+            SignedBitcoinQuantity::from_satoshi(-200)
+        );
+        assert_that(&BitcoinQuantity::from_satoshi(u64::MAX).checked_signed_sub(fee)).is_none();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_signed_quantity() {
+        let quantity = SignedBitcoinQuantity::from_satoshi(-100_000_000);
+        assert_eq!(serde_json::to_string(&quantity).unwrap(), "\"-100000000\"");
+        assert_eq!(
+            serde_json::from_str::<SignedBitcoinQuantity>("\"-100000000\"").unwrap(),
+            quantity
+        );
+        assert_eq!(
+            serde_json::from_str::<SignedBitcoinQuantity>("-100000000").unwrap(),
+            quantity
+        );
+
+        let bytes = bincode::serialize(&quantity).unwrap();
+        assert_eq!(bytes, (-100_000_000i64).to_le_bytes());
+        assert_eq!(
+            bincode::deserialize::<SignedBitcoinQuantity>(&bytes).unwrap(),
+            quantity
+        );
+    }
+}
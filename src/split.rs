@@ -0,0 +1,134 @@
+use std::cmp::Reverse;
+use BitcoinQuantity;
+
+impl BitcoinQuantity {
+    /// Splits the quantity into `parts` parts that differ by at most one
+    /// satoshi and sum up exactly to the original quantity. Left over
+    /// satoshis go to the first parts. Returns `None` if `parts` is zero.
+    pub fn split_even(self, parts: usize) -> Option<Vec<BitcoinQuantity>> {
+        if parts == 0 {
+            return None;
+        }
+
+        let base = self.satoshi() / parts as u64;
+        let extra = (self.satoshi() % parts as u64) as usize;
+
+        Some(
+            (0..parts)
+                .map(|index| {
+                    let sats = if index < extra { base + 1 } else { base };
+                    BitcoinQuantity::from_satoshi(sats)
+                })
+                .collect(),
+        )
+    }
+
+    /// Splits the quantity proportionally to `weights` so that the parts sum
+    /// up exactly to the original quantity, using the largest remainder
+    /// method: every part gets its share rounded down, then the left over
+    /// satoshis go one by one to the parts with the largest fractional
+    /// remainder. Ties go to the part that comes first.
+    ///
+    /// Returns `None` if `weights` is empty or all weights are zero.
+    pub fn split_by_weights(self, weights: &[u64]) -> Option<Vec<BitcoinQuantity>> {
+        let total = weights.iter().try_fold(0u128, |total, &weight| {
+            total.checked_add(u128::from(weight))
+        })?;
+        if total == 0 {
+            return None;
+        }
+
+        let amount = u128::from(self.satoshi());
+        let (mut shares, remainders): (Vec<u64>, Vec<u128>) = weights
+            .iter()
+            .map(|&weight| {
+                let scaled = amount * u128::from(weight);
+                ((scaled / total) as u64, scaled % total)
+            })
+            .unzip();
+
+        let distributed: u64 = shares.iter().sum();
+        let left_over = (self.satoshi() - distributed) as usize;
+
+        let mut order: Vec<usize> = (0..weights.len()).collect();
+        order.sort_by_key(|&index| Reverse(remainders[index]));
+        for &index in order.iter().take(left_over) {
+            shares[index] += 1;
+        }
+
+        Some(
+            shares
+                .into_iter()
+                .map(BitcoinQuantity::from_satoshi)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use BitcoinQuantity;
+
+    fn sats(quantities: Vec<BitcoinQuantity>) -> Vec<u64> {
+        quantities
+            .into_iter()
+            .map(BitcoinQuantity::satoshi)
+            .collect()
+    }
+
+    #[test]
+    fn split_even_distributes_remainder_to_first_parts() {
+        let quantity = BitcoinQuantity::from_satoshi(10);
+        assert_eq!(sats(quantity.split_even(3).unwrap()), vec![4, 3, 3]);
+        assert_eq!(sats(quantity.split_even(5).unwrap()), vec![2, 2, 2, 2, 2]);
+        assert_eq!(
+            sats(BitcoinQuantity::from_satoshi(2).split_even(4).unwrap()),
+            vec![1, 1, 0, 0]
+        );
+        assert_eq!(quantity.split_even(0), None);
+    }
+
+    #[test]
+    fn split_by_weights_uses_largest_remainder() {
+        let quantity = BitcoinQuantity::from_satoshi(100);
+        assert_eq!(
+            sats(quantity.split_by_weights(&[1, 1, 1]).unwrap()),
+            vec![34, 33, 33]
+        );
+        assert_eq!(
+            sats(quantity.split_by_weights(&[1, 2, 3]).unwrap()),
+            vec![17, 33, 50]
+        );
+        assert_eq!(
+            sats(quantity.split_by_weights(&[0, 3, 0, 7]).unwrap()),
+            vec![0, 30, 0, 70]
+        );
+        assert_eq!(quantity.split_by_weights(&[]), None);
+        assert_eq!(quantity.split_by_weights(&[0, 0]), None);
+    }
+
+    #[test]
+    fn split_never_creates_or_loses_satoshis() {
+        let quantity = BitcoinQuantity::from_satoshi(u64::MAX);
+        for weights in &[
+            vec![u64::MAX, 1],
+            vec![u64::MAX, u64::MAX, u64::MAX],
+            vec![3, 7, 11, 13, 17],
+        ] {
+            let parts = quantity.split_by_weights(weights).unwrap();
+            assert_eq!(
+                parts
+                    .iter()
+                    .map(|part| part.satoshi() as u128)
+                    .sum::<u128>(),
+                u64::MAX as u128
+            );
+        }
+
+        let parts = BitcoinQuantity::MAX_MONEY.split_even(7).unwrap();
+        assert_eq!(
+            parts.iter().map(|part| part.satoshi()).sum::<u64>(),
+            BitcoinQuantity::MAX_MONEY.satoshi()
+        );
+    }
+}